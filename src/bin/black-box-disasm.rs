@@ -0,0 +1,78 @@
+//! Prints every level in the campaign as Markdown, with each of its scripts disassembled into
+//! pseudo-code and the solver's solution, both hidden behind spoiler tags.
+//!
+//! Run with `cargo run --bin black-box-disasm [LEVEL_FILE...]` from the repository root, where
+//! the optional level files (e.g. `pin_pad.box.ron`) limit the output to those levels.
+use black_box::{
+    box_internal::{disassembly::disassemble, solver, BoxData},
+    campaign::{Campaign, CAMPAIGN_PATH},
+};
+use std::{convert::TryFrom, env, process};
+
+fn print_level(title: &str, data: &BoxData) {
+    println!("## {}\n", title);
+
+    let prompt: Vec<String> = data.prompt.iter().map(ToString::to_string).collect();
+    println!("Prompt: `{}`\n", prompt.join(" "));
+
+    println!("<details><summary>Buttons</summary>\n");
+    println!("```");
+    for (i, name) in data.memory.names.iter().enumerate() {
+        println!("// r{}: {}", i, name);
+    }
+    for (i, button) in data.buttons.iter().enumerate() {
+        println!("// button {}", i + 1);
+        print!("{}", disassemble(&button.button));
+    }
+    for (i, tick) in data.ticks.iter().enumerate() {
+        println!("// tick {}, every {:?}", i, tick.every);
+        print!("{}", disassemble(&tick.script));
+    }
+    for (name, script) in [("on_enter", &data.on_enter), ("on_exit", &data.on_exit)].iter() {
+        if !script.is_empty() {
+            println!("// {}", name);
+            print!("{}", disassemble(script));
+        }
+    }
+    println!("```\n\n</details>\n");
+
+    println!("<details><summary>Solution</summary>\n");
+    match solver::solve(data, solver::DEFAULT_MAX_PRESSES) {
+        Ok(presses) => {
+            let presses: Vec<String> = presses.iter().map(|i| (i + 1).to_string()).collect();
+            println!("{}", presses.join(" "));
+        }
+        Err(e) => println!("{}", e),
+    }
+    println!("\n</details>\n");
+}
+
+fn main() {
+    let only: Vec<String> = env::args().skip(1).collect();
+
+    let campaign = match Campaign::load(CAMPAIGN_PATH) {
+        Ok(campaign) => campaign,
+        Err(e) => {
+            eprintln!("{}: {}", CAMPAIGN_PATH, e);
+            process::exit(2);
+        }
+    };
+
+    let mut failed = false;
+    for level in campaign.levels.iter() {
+        if !only.is_empty() && !only.contains(&level.path) {
+            continue;
+        }
+        match BoxData::try_from(level.path.as_str()) {
+            Ok(data) => print_level(&level.title, &data),
+            Err(e) => {
+                eprintln!("{}", e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
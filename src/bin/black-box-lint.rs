@@ -0,0 +1,183 @@
+//! Checks the campaign and every level in `assets/levels` for problems, exiting with a non-zero
+//! status if any are found.
+//!
+//! Run with `cargo run --bin black-box-lint` from the repository root.
+use black_box::{
+    box_internal::{
+        loading::{LEVEL_DIR, LEVEL_EXTENSION},
+        solver, BoxData,
+    },
+    campaign::{Campaign, LevelEntry, CAMPAIGN_PATH},
+    input_map::{box_button, InputConfig, INPUT_CONFIG_PATH},
+};
+use std::{
+    collections::{BTreeSet, HashSet},
+    convert::TryFrom,
+    fs, process,
+};
+
+/// Buttons closer together than this (in box-local units) visually overlap.
+const MIN_BUTTON_SPACING: f32 = 0.09;
+
+/// Problems with how the level is presented, on top of those already caught while loading it.
+fn lint_level(data: &BoxData, input_config: &InputConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for i in 0..data.buttons.len() {
+        let bound = match input_config.actions.get(&box_button(i)) {
+            Some(binding) => !binding.is_empty(),
+            None => false,
+        };
+        if !bound {
+            problems.push(format!(
+                "button {} can't be pressed, {} has no binding in {}",
+                i,
+                box_button(i),
+                INPUT_CONFIG_PATH
+            ));
+        }
+    }
+
+    for (i, a) in data.buttons.iter().enumerate() {
+        for (j, b) in data.buttons.iter().enumerate().skip(i + 1) {
+            if a.translation.distance(b.translation) < MIN_BUTTON_SPACING {
+                problems.push(format!("buttons {} and {} overlap", i, j));
+            }
+        }
+    }
+
+    problems
+}
+
+/// Levels that can never be unlocked, because they (indirectly) require themselves or an unknown
+/// level.
+fn unreachable_levels(campaign: &Campaign) -> Vec<&LevelEntry> {
+    let mut unlocked = HashSet::new();
+    loop {
+        let newly_unlocked: Vec<&str> = campaign
+            .levels
+            .iter()
+            .filter(|level| !unlocked.contains(level.id.as_str()))
+            .filter(|level| {
+                level
+                    .requires
+                    .iter()
+                    .all(|required| unlocked.contains(required.as_str()))
+            })
+            .map(|level| level.id.as_str())
+            .collect();
+        if newly_unlocked.is_empty() {
+            break;
+        }
+        unlocked.extend(newly_unlocked);
+    }
+
+    campaign
+        .levels
+        .iter()
+        .filter(|level| !unlocked.contains(level.id.as_str()))
+        .collect()
+}
+
+fn main() {
+    let mut failed = false;
+    let mut report = |problem: String| {
+        eprintln!("{}", problem);
+        failed = true;
+    };
+
+    let on_disk: BTreeSet<String> = match fs::read_dir(LEVEL_DIR) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(LEVEL_EXTENSION))
+            .collect(),
+        Err(e) => {
+            eprintln!("{}: {}", LEVEL_DIR, e);
+            process::exit(2);
+        }
+    };
+
+    let campaign = match Campaign::load(CAMPAIGN_PATH) {
+        Ok(campaign) => campaign,
+        Err(e) => {
+            eprintln!("{}: {}", CAMPAIGN_PATH, e);
+            process::exit(2);
+        }
+    };
+
+    let input_config = match InputConfig::load(INPUT_CONFIG_PATH) {
+        Ok(input_config) => input_config,
+        Err(e) => {
+            eprintln!("{}: {}", INPUT_CONFIG_PATH, e);
+            process::exit(2);
+        }
+    };
+
+    let mut ids = HashSet::new();
+    for level in campaign.levels.iter() {
+        if !ids.insert(level.id.as_str()) {
+            report(format!(
+                "{}: level id {} is used twice",
+                CAMPAIGN_PATH, level.id
+            ));
+        }
+        if !on_disk.contains(&level.path) {
+            report(format!(
+                "{}: listed in the campaign, but not found",
+                level.path
+            ));
+        }
+        for required in level.requires.iter() {
+            if campaign.get(required).is_none() {
+                report(format!(
+                    "{}: level {} requires unknown level {}",
+                    CAMPAIGN_PATH, level.id, required
+                ));
+            }
+        }
+    }
+
+    for (i, prop) in campaign.props.iter().enumerate() {
+        for required in prop.requires.iter() {
+            if campaign.get(required).is_none() {
+                report(format!(
+                    "{}: prop {} requires unknown level {}",
+                    CAMPAIGN_PATH, i, required
+                ));
+            }
+        }
+    }
+    for level in unreachable_levels(&campaign) {
+        report(format!(
+            "{}: level {} can never be unlocked",
+            CAMPAIGN_PATH, level.id
+        ));
+    }
+
+    for level in on_disk.iter() {
+        if !campaign.levels.iter().any(|entry| &entry.path == level) {
+            report(format!("{}: not listed in the campaign", level));
+        }
+
+        match BoxData::try_from(level.as_str()) {
+            Ok(data) => {
+                let problems = lint_level(&data, &input_config);
+                for problem in problems.iter() {
+                    report(format!("{}: {}", level, problem));
+                }
+                if problems.is_empty() {
+                    match solver::solve(&data, solver::DEFAULT_MAX_PRESSES) {
+                        Ok(presses) => println!("{}: ok (par {})", level, presses.len()),
+                        Err(e) => report(format!("{}: {}", level, e)),
+                    }
+                }
+            }
+            Err(e) => report(e.to_string()),
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
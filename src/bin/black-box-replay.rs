@@ -0,0 +1,56 @@
+//! Plays back a replay recorded with `cargo run -- --record REPLAY_FILE`, checking that every
+//! press still has the recorded outputs and BoxState, and exiting with a non-zero status if not.
+//!
+//! Run with `cargo run --bin black-box-replay REPLAY_FILE` from the repository root.
+use black_box::{
+    box_internal::{
+        replay::{self, Replay},
+        BoxData,
+    },
+    campaign::{Campaign, CAMPAIGN_PATH},
+};
+use std::{collections::HashMap, convert::TryFrom, env, process};
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: black-box-replay REPLAY_FILE");
+            process::exit(2);
+        }
+    };
+
+    let replay = match Replay::load(&path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            process::exit(2);
+        }
+    };
+
+    let campaign = match Campaign::load(CAMPAIGN_PATH) {
+        Ok(campaign) => campaign,
+        Err(e) => {
+            eprintln!("{}: {}", CAMPAIGN_PATH, e);
+            process::exit(2);
+        }
+    };
+
+    let mut levels = HashMap::new();
+    for level in campaign.levels.iter() {
+        match BoxData::try_from(level.path.as_str()) {
+            Ok(data) => {
+                levels.insert(level.id.clone(), data);
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    match replay::play(&replay, &levels) {
+        Ok(()) => println!("{}: ok ({} presses)", path, replay.presses.len()),
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
@@ -1,9 +1,15 @@
 //! Provides objects for defining the behavior of a Box's Buttons.
-use crate::box_internal::components::BoxState;
+use crate::box_internal::{
+    components::{BoxState, StepLimit},
+    values::Value,
+};
+use bevy::log::warn;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{cmp::Ordering, convert::TryFrom, fmt};
 
 /// Enum that provides various commands that read/write to a BoxState when evaluated.
+///
+/// See Value for how the arithmetic and bitwise operations behave on each type.
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub enum Action {
@@ -13,98 +19,472 @@ pub enum Action {
     Add(Val, Val, usize),
     /// Add the Val and the current value at the usize index, then store the result at that index.
     AddEq(Val, usize),
+    /// Subtract the second Val from the first, and store the result at the usize index.
+    Sub(Val, Val, usize),
     /// Multiply the two Vals and store the result at the usize index.
     Mult(Val, Val, usize),
+    /// Divide the first Val by the second, and store the result at the usize index.
+    /// Dividing by zero stores 0.
+    Div(Val, Val, usize),
     /// Take the first Val mod the second Val, and store the result in the usize index.
     Mod(Val, Val, usize),
-    /// Set the value at the usize index to 1.0 if the two Vals are equal, 0.0 otherwise.
+    /// Negate the Val and store the result at the usize index.
+    Neg(Val, usize),
+    /// Store the absolute value of the Val at the usize index.
+    Abs(Val, usize),
+    /// Store the smaller of the two Vals at the usize index.
+    Min(Val, Val, usize),
+    /// Store the larger of the two Vals at the usize index.
+    Max(Val, Val, usize),
+    /// Round the Val down and store the result at the usize index.
+    Floor(Val, usize),
+    /// Round the Val to the nearest integer and store the result at the usize index.
+    Round(Val, usize),
+    /// Set the value at the usize index to true if the two Vals are equal, false otherwise.
+    /// Integral values are compared exactly, see Value::equals.
     Equals(Val, Val, usize),
+    /// Set the value at the usize index to true if the first Val is less than the second.
+    Lt(Val, Val, usize),
+    /// Set the value at the usize index to true if the first Val is greater than the second.
+    Gt(Val, Val, usize),
+    /// Set the value at the usize index to true if the first Val is less than or equal to the
+    /// second.
+    Le(Val, Val, usize),
+    /// Set the value at the usize index to true if the first Val is greater than or equal to the
+    /// second.
+    Ge(Val, Val, usize),
+    /// Store the logical (for Bools) or bitwise (otherwise) not of the Val at the usize index.
+    Not(Val, usize),
+    /// Store the logical or bitwise and of the two Vals at the usize index.
+    And(Val, Val, usize),
+    /// Store the logical or bitwise or of the two Vals at the usize index.
+    Or(Val, Val, usize),
+    /// Store the logical or bitwise xor of the two Vals at the usize index.
+    Xor(Val, Val, usize),
+    /// Shift the first Val left by the second, and store the result at the usize index.
+    Shl(Val, Val, usize),
+    /// Shift the first Val right by the second, and store the result at the usize index.
+    Shr(Val, Val, usize),
     /// If the first Val is nonzero, set the value at the usize index to the second Val, otherwise
     /// set it to the third Val.
     IfElse(Val, Val, Val, usize),
-    /// Evaluate every Action in the Vec in order, and return the last returned BoxOut (if there
-    /// are any).
+    /// Evaluate every Action in the Vec in order, and return every BoxOut they return.
     Do(Vec<Action>),
     /// If the first Val is nonzero, evaluate the first Vec\<Action\>, otherwise evaluate the second.
     IfElseDo(Val, Vec<Action>, Vec<Action>),
+    /// Evaluate the Vec\<Action\> as many times as the Val evaluates to (once, before looping),
+    /// and return every BoxOut from every iteration.
+    Repeat(Val, Vec<Action>),
+    /// Evaluate the Vec\<Action\> for as long as the Val is nonzero, and return every BoxOut from
+    /// every iteration.
+    While(Val, Vec<Action>),
     /// Return the Val as a BoxOut::Int
     PrintInt(Val),
+    /// Return the Val as a BoxOut::Flt, rounded (half away from zero, like Round) to the usize
+    /// number of decimal places, and shown with exactly that many.
+    /// At most MAX_FLT_PRECISION decimal places are kept.
+    PrintFlt(Val, usize),
+    /// Return the String as a BoxOut::Str.
+    PrintStr(String),
+    /// Return the character whose code point is the Val as a BoxOut::Str, or U+FFFD (the
+    /// replacement character) if it isn't a valid one.
+    PrintChar(Val),
+}
+
+/// The most decimal places Action::PrintFlt keeps, far more than fit on a box's display.
+pub const MAX_FLT_PRECISION: usize = 16;
+
+/// Keeps track of how many more steps an evaluation may take, so that loops can't run forever.
+///
+/// Every Action evaluated, and every iteration of a loop, takes one step.
+/// Once the budget is exhausted, Actions are skipped instead of evaluated.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct StepBudget {
+    remaining: usize,
+    /// Whether a step was ever refused, and so something skipped.
+    exceeded: bool,
+}
+
+impl StepBudget {
+    pub fn new(limit: StepLimit) -> StepBudget {
+        StepBudget {
+            remaining: limit.0,
+            exceeded: false,
+        }
+    }
+
+    /// Take a step, returning false if there were none left.
+    fn step(&mut self) -> bool {
+        if self.remaining == 0 {
+            self.exceeded = true;
+            false
+        } else {
+            self.remaining -= 1;
+            true
+        }
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Whether a step was asked for after the budget was exhausted, so something was skipped.
+    ///
+    /// Unlike is_exhausted, this is false for evaluations that use up exactly the whole budget.
+    pub fn is_exceeded(&self) -> bool {
+        self.exceeded
+    }
+}
+
+/// Evaluate every Action in order, and return all of their BoxOuts in order.
+fn evaluate_all(actions: &[Action], state: &mut BoxState, budget: &mut StepBudget) -> Vec<BoxOut> {
+    actions
+        .iter()
+        .flat_map(|action| action.evaluate(state, budget))
+        .collect()
+}
+
+fn set_unary(
+    state: &mut BoxState,
+    a: &Val,
+    i: usize,
+    op: impl FnOnce(Value) -> Value,
+) -> Vec<BoxOut> {
+    let value = op(a.evaluate(state));
+    state.set(i, value);
+    Vec::new()
+}
+
+fn set_binary(
+    state: &mut BoxState,
+    a: &Val,
+    b: &Val,
+    i: usize,
+    op: impl FnOnce(Value, Value) -> Value,
+) -> Vec<BoxOut> {
+    let value = op(a.evaluate(state), b.evaluate(state));
+    state.set(i, value);
+    Vec::new()
+}
+
+fn set_test(
+    state: &mut BoxState,
+    a: &Val,
+    b: &Val,
+    i: usize,
+    test: impl FnOnce(Value, Value) -> bool,
+) -> Vec<BoxOut> {
+    set_binary(state, a, b, i, |a, b| Value::Bool(test(a, b)))
+}
+
+fn set_comparison(
+    state: &mut BoxState,
+    a: &Val,
+    b: &Val,
+    i: usize,
+    test: impl FnOnce(Ordering) -> bool,
+) -> Vec<BoxOut> {
+    set_test(state, a, b, i, |a, b| a.compare(b).is_some_and(test))
 }
 
 impl Action {
-    pub fn evaluate(&self, state: &mut BoxState) -> Option<BoxOut> {
+    /// Evaluate the Action, returning everything it (and any nested Actions) output, in order.
+    pub fn evaluate(&self, state: &mut BoxState, budget: &mut StepBudget) -> Vec<BoxOut> {
+        if !budget.step() {
+            return Vec::new();
+        }
         match self {
-            Action::Set(val, i) => {
-                state[*i] = val.evaluate(state);
-                None
-            }
-            Action::Add(a, b, i) => {
-                state[*i] = a.evaluate(state) + b.evaluate(state);
-                None
-            }
-            Action::AddEq(a, i) => Action::Add(*a, Val::G(*i), *i).evaluate(state),
-            Action::Mult(a, b, i) => {
-                state[*i] = a.evaluate(state) * b.evaluate(state);
-                None
-            }
-            Action::Mod(a, b, i) => {
-                state[*i] = ((a.evaluate(state) % b.evaluate(state)) + b.evaluate(state))
-                    % b.evaluate(state);
-                None
-            }
-            Action::Equals(a, b, i) => {
-                state[*i] = if (a.evaluate(state) - b.evaluate(state)).abs() < 0.00001 {
-                    1.
-                } else {
-                    0.
-                };
-                None
-            }
+            Action::Set(val, i) => set_unary(state, val, *i, |v| v),
+            Action::Add(a, b, i) => set_binary(state, a, b, *i, |a, b| a + b),
+            Action::AddEq(a, i) => set_binary(state, a, &Val::G(*i), *i, |a, b| a + b),
+            Action::Sub(a, b, i) => set_binary(state, a, b, *i, |a, b| a - b),
+            Action::Mult(a, b, i) => set_binary(state, a, b, *i, |a, b| a * b),
+            Action::Div(a, b, i) => set_binary(state, a, b, *i, |a, b| a / b),
+            Action::Mod(a, b, i) => set_binary(state, a, b, *i, Value::modulo),
+            Action::Neg(a, i) => set_unary(state, a, *i, |a| -a),
+            Action::Abs(a, i) => set_unary(state, a, *i, Value::abs),
+            Action::Min(a, b, i) => set_binary(state, a, b, *i, Value::min),
+            Action::Max(a, b, i) => set_binary(state, a, b, *i, Value::max),
+            Action::Floor(a, i) => set_unary(state, a, *i, Value::floor),
+            Action::Round(a, i) => set_unary(state, a, *i, Value::round),
+            Action::Equals(a, b, i) => set_test(state, a, b, *i, Value::equals),
+            Action::Lt(a, b, i) => set_comparison(state, a, b, *i, Ordering::is_lt),
+            Action::Gt(a, b, i) => set_comparison(state, a, b, *i, Ordering::is_gt),
+            Action::Le(a, b, i) => set_comparison(state, a, b, *i, Ordering::is_le),
+            Action::Ge(a, b, i) => set_comparison(state, a, b, *i, Ordering::is_ge),
+            Action::Not(a, i) => set_unary(state, a, *i, |a| !a),
+            Action::And(a, b, i) => set_binary(state, a, b, *i, |a, b| a & b),
+            Action::Or(a, b, i) => set_binary(state, a, b, *i, |a, b| a | b),
+            Action::Xor(a, b, i) => set_binary(state, a, b, *i, |a, b| a ^ b),
+            Action::Shl(a, b, i) => set_binary(state, a, b, *i, |a, b| a << b),
+            Action::Shr(a, b, i) => set_binary(state, a, b, *i, |a, b| a >> b),
             Action::IfElse(a, b, c, i) => {
-                state[*i] = if a.evaluate(state) != 0. {
+                let val = if a.evaluate(state).is_truthy() {
                     b.evaluate(state)
                 } else {
                     c.evaluate(state)
                 };
-                None
+                state.set(*i, val);
+                Vec::new()
+            }
+            Action::Do(dos) => evaluate_all(dos, state, budget),
+            Action::IfElseDo(a, if_dos, else_dos) => {
+                if a.evaluate(state).is_truthy() {
+                    evaluate_all(if_dos, state, budget)
+                } else {
+                    evaluate_all(else_dos, state, budget)
+                }
             }
-            Action::Do(dos) => {
-                let mut res = None;
-                for action in dos {
-                    if let Some(r) = action.evaluate(state) {
-                        res = Some(r);
+            Action::Repeat(a, dos) => {
+                let mut res = Vec::new();
+                for _ in 0..a.evaluate(state).as_int().max(0) {
+                    if !budget.step() {
+                        break;
                     }
+                    res.extend(evaluate_all(dos, state, budget));
                 }
                 res
             }
-            Action::IfElseDo(a, if_dos, else_dos) => {
-                if a.evaluate(state) != 0. {
-                    Action::Do(if_dos.clone()).evaluate(state)
-                } else {
-                    Action::Do(else_dos.clone()).evaluate(state)
+            Action::While(a, dos) => {
+                let mut res = Vec::new();
+                while a.evaluate(state).is_truthy() && budget.step() {
+                    res.extend(evaluate_all(dos, state, budget));
                 }
+                res
+            }
+            Action::PrintInt(val) => vec![BoxOut::Int(val.evaluate(state).as_int())],
+            Action::PrintFlt(val, precision) => {
+                let value = val.evaluate(state).as_flt();
+                let precision = (*precision).min(MAX_FLT_PRECISION);
+                let scale = 10f64.powi(precision as i32);
+                let rounded = ((value as f64 * scale).round() / scale) as f32;
+                vec![BoxOut::Flt(rounded, precision)]
+            }
+            Action::PrintStr(text) => vec![BoxOut::Str(text.clone())],
+            Action::PrintChar(val) => {
+                let c = u32::try_from(val.evaluate(state).as_int())
+                    .ok()
+                    .and_then(char::from_u32)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                vec![BoxOut::Str(c.to_string())]
+            }
+        }
+    }
+
+    /// Every BoxState index this Action (and any nested Actions) reads from or writes to.
+    pub fn register_indices(&self) -> Vec<usize> {
+        let vals_and_target = |vals: &[&Val], target: &usize| {
+            vals.iter()
+                .flat_map(|val| val.register_indices())
+                .chain(std::iter::once(*target))
+                .collect()
+        };
+        match self {
+            Action::Set(a, i)
+            | Action::AddEq(a, i)
+            | Action::Neg(a, i)
+            | Action::Abs(a, i)
+            | Action::Floor(a, i)
+            | Action::Round(a, i)
+            | Action::Not(a, i) => vals_and_target(&[a], i),
+            Action::Add(a, b, i)
+            | Action::Sub(a, b, i)
+            | Action::Mult(a, b, i)
+            | Action::Div(a, b, i)
+            | Action::Mod(a, b, i)
+            | Action::Min(a, b, i)
+            | Action::Max(a, b, i)
+            | Action::Equals(a, b, i)
+            | Action::Lt(a, b, i)
+            | Action::Gt(a, b, i)
+            | Action::Le(a, b, i)
+            | Action::Ge(a, b, i)
+            | Action::And(a, b, i)
+            | Action::Or(a, b, i)
+            | Action::Xor(a, b, i)
+            | Action::Shl(a, b, i)
+            | Action::Shr(a, b, i) => vals_and_target(&[a, b], i),
+            Action::IfElse(a, b, c, i) => vals_and_target(&[a, b, c], i),
+            Action::Do(dos) => dos.iter().flat_map(Action::register_indices).collect(),
+            Action::IfElseDo(a, if_dos, else_dos) => a
+                .register_indices()
+                .into_iter()
+                .chain(if_dos.iter().flat_map(Action::register_indices))
+                .chain(else_dos.iter().flat_map(Action::register_indices))
+                .collect(),
+            Action::Repeat(a, dos) | Action::While(a, dos) => a
+                .register_indices()
+                .into_iter()
+                .chain(dos.iter().flat_map(Action::register_indices))
+                .collect(),
+            Action::PrintInt(val) | Action::PrintFlt(val, _) | Action::PrintChar(val) => {
+                val.register_indices()
             }
-            Action::PrintInt(val) => Some(BoxOut::Int(val.evaluate(state) as i32)),
+            Action::PrintStr(_) => Vec::new(),
         }
     }
 }
 
-/// Enum that provides abstraction over whether a value should be provided as is (C(onstant)) or
-/// should be looked up in a BoxState (G(et)) when evaluated.
-#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+/// Evaluate every Action in the script in order, collecting the returned BoxOuts.
+/// If the script takes more steps than the StepLimit allows, the rest of it is skipped.
+///
+/// This is what pressing a button does, and is shared between the ECS systems and the
+/// BoxSimulator.
+pub fn run_script(script: &[Action], state: &mut BoxState, limit: StepLimit) -> Vec<BoxOut> {
+    let mut budget = StepBudget::new(limit);
+    let outputs = script
+        .iter()
+        .flat_map(|action| action.evaluate(state, &mut budget))
+        .collect();
+    if budget.is_exceeded() {
+        warn!(
+            "Button script took more than {} steps and was cut short",
+            limit.0
+        );
+    }
+    outputs
+}
+
+/// Enum that provides abstraction over whether a value should be provided as is (C(onstant), I(nt)
+/// or B(ool)), should be looked up in a BoxState (G(et)), or should be computed from other Vals
+/// when evaluated.
+///
+/// The computed variants mirror the Actions of the same name, but produce a value instead of
+/// storing it, so expressions like `PrintInt(Add(Mult(G(0), I(2)), G(1)))` don't need any scratch
+/// registers.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub enum Val {
-    /// Constant, evaluates to the internal value.
+    /// Constant, evaluates to the internal value as a Value::Flt.
     C(f32),
+    /// Integer constant, evaluates to the internal value as a Value::Int.
+    I(i32),
+    /// Boolean constant, evaluates to the internal value as a Value::Bool.
+    B(bool),
     /// Get, evaluates to a value stored in a BoxState given the internal value as an index.
     G(usize),
+    /// Indirect get, evaluates to the value stored in a BoxState at the index the inner Val
+    /// evaluates to.
+    /// Indices outside of the BoxState evaluate to Int(0).
+    Get(Box<Val>),
+    Add(Box<Val>, Box<Val>),
+    Sub(Box<Val>, Box<Val>),
+    Mult(Box<Val>, Box<Val>),
+    Div(Box<Val>, Box<Val>),
+    Mod(Box<Val>, Box<Val>),
+    Neg(Box<Val>),
+    Abs(Box<Val>),
+    Min(Box<Val>, Box<Val>),
+    Max(Box<Val>, Box<Val>),
+    Floor(Box<Val>),
+    Round(Box<Val>),
+    Equals(Box<Val>, Box<Val>),
+    Lt(Box<Val>, Box<Val>),
+    Gt(Box<Val>, Box<Val>),
+    Le(Box<Val>, Box<Val>),
+    Ge(Box<Val>, Box<Val>),
+    Not(Box<Val>),
+    And(Box<Val>, Box<Val>),
+    Or(Box<Val>, Box<Val>),
+    Xor(Box<Val>, Box<Val>),
+    Shl(Box<Val>, Box<Val>),
+    Shr(Box<Val>, Box<Val>),
+    /// If the first Val is nonzero, evaluates to the second Val, otherwise the third.
+    If(Box<Val>, Box<Val>, Box<Val>),
 }
 
 impl Val {
-    fn evaluate(&self, state: &BoxState) -> f32 {
+    fn evaluate(&self, state: &BoxState) -> Value {
+        let compare = |a: &Val, b: &Val, test: fn(Ordering) -> bool| {
+            Value::Bool(
+                a.evaluate(state)
+                    .compare(b.evaluate(state))
+                    .is_some_and(test),
+            )
+        };
         match self {
-            Val::C(val) => *val,
+            Val::C(val) => Value::Flt(*val),
+            Val::I(val) => Value::Int(*val),
+            Val::B(val) => Value::Bool(*val),
             Val::G(i) => state[*i],
+            Val::Get(i) => {
+                let i = i.evaluate(state).as_int();
+                usize::try_from(i)
+                    .ok()
+                    .and_then(|i| state.get(i).copied())
+                    .unwrap_or(Value::Int(0))
+            }
+            Val::Add(a, b) => a.evaluate(state) + b.evaluate(state),
+            Val::Sub(a, b) => a.evaluate(state) - b.evaluate(state),
+            Val::Mult(a, b) => a.evaluate(state) * b.evaluate(state),
+            Val::Div(a, b) => a.evaluate(state) / b.evaluate(state),
+            Val::Mod(a, b) => a.evaluate(state).modulo(b.evaluate(state)),
+            Val::Neg(a) => -a.evaluate(state),
+            Val::Abs(a) => a.evaluate(state).abs(),
+            Val::Min(a, b) => a.evaluate(state).min(b.evaluate(state)),
+            Val::Max(a, b) => a.evaluate(state).max(b.evaluate(state)),
+            Val::Floor(a) => a.evaluate(state).floor(),
+            Val::Round(a) => a.evaluate(state).round(),
+            Val::Equals(a, b) => Value::Bool(a.evaluate(state).equals(b.evaluate(state))),
+            Val::Lt(a, b) => compare(a, b, Ordering::is_lt),
+            Val::Gt(a, b) => compare(a, b, Ordering::is_gt),
+            Val::Le(a, b) => compare(a, b, Ordering::is_le),
+            Val::Ge(a, b) => compare(a, b, Ordering::is_ge),
+            Val::Not(a) => !a.evaluate(state),
+            Val::And(a, b) => a.evaluate(state) & b.evaluate(state),
+            Val::Or(a, b) => a.evaluate(state) | b.evaluate(state),
+            Val::Xor(a, b) => a.evaluate(state) ^ b.evaluate(state),
+            Val::Shl(a, b) => a.evaluate(state) << b.evaluate(state),
+            Val::Shr(a, b) => a.evaluate(state) >> b.evaluate(state),
+            Val::If(a, b, c) => {
+                if a.evaluate(state).is_truthy() {
+                    b.evaluate(state)
+                } else {
+                    c.evaluate(state)
+                }
+            }
+        }
+    }
+
+    /// Every BoxState index this Val reads from directly.
+    /// Indices computed by Get aren't known until evaluation, so they aren't included.
+    fn register_indices(&self) -> Vec<usize> {
+        match self {
+            Val::C(_) | Val::I(_) | Val::B(_) => Vec::new(),
+            Val::G(i) => vec![*i],
+            Val::Get(a)
+            | Val::Neg(a)
+            | Val::Abs(a)
+            | Val::Floor(a)
+            | Val::Round(a)
+            | Val::Not(a) => a.register_indices(),
+            Val::Add(a, b)
+            | Val::Sub(a, b)
+            | Val::Mult(a, b)
+            | Val::Div(a, b)
+            | Val::Mod(a, b)
+            | Val::Min(a, b)
+            | Val::Max(a, b)
+            | Val::Equals(a, b)
+            | Val::Lt(a, b)
+            | Val::Gt(a, b)
+            | Val::Le(a, b)
+            | Val::Ge(a, b)
+            | Val::And(a, b)
+            | Val::Or(a, b)
+            | Val::Xor(a, b)
+            | Val::Shl(a, b)
+            | Val::Shr(a, b) => {
+                let mut indices = a.register_indices();
+                indices.extend(b.register_indices());
+                indices
+            }
+            Val::If(a, b, c) => {
+                let mut indices = a.register_indices();
+                indices.extend(b.register_indices());
+                indices.extend(c.register_indices());
+                indices
+            }
         }
     }
 }
@@ -113,13 +493,15 @@ impl Val {
 /// Used internally as the return type when evaluating actions, in OutputEvents, and in
 /// Progressions.
 ///
-/// May be able to replaced by a simple i32 eventually, as BoxOut::Int is the only variant used so
-/// far, but left as an enum for now to make potential other uses easy.
+/// Action::PrintInt returns Ints, Action::PrintFlt returns Flts, and Action::PrintStr and
+/// Action::PrintChar return Strs.
+/// A Flt carries the number of decimal places it's shown with, so prompts have to say it too,
+/// e.g. `Flt(1.5, 2)` for "1.50".
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub enum BoxOut {
     Int(i32),
-    Flt(f32),
+    Flt(f32, usize),
     Str(String),
 }
 
@@ -127,7 +509,7 @@ impl fmt::Display for BoxOut {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             BoxOut::Int(o) => write!(f, "{}", o),
-            BoxOut::Flt(o) => write!(f, "{}", o),
+            BoxOut::Flt(o, precision) => write!(f, "{:.*}", precision, o),
             BoxOut::Str(o) => write!(f, "{}", o),
         }
     }
@@ -138,3 +520,119 @@ impl Default for BoxOut {
         BoxOut::Int(0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::box_internal::{components::MemoryLayout, script::parse, values::RegisterType};
+
+    /// Run source on a box whose registers have the given types, returning the resulting state
+    /// and outputs.
+    fn run(types: &[RegisterType], source: &str, limit: usize) -> (BoxState, Vec<BoxOut>) {
+        let mut state = BoxState::new(&MemoryLayout {
+            size: types.len(),
+            types: types.to_vec(),
+            ..Default::default()
+        });
+        let outputs = run_script(&parse(source).unwrap(), &mut state, StepLimit(limit));
+        (state, outputs)
+    }
+
+    #[test]
+    fn operations_store_into_the_register_type() {
+        use RegisterType::*;
+        let (state, _) = run(
+            &[Int, Flt, Int, Flt, Int, Flt],
+            "r0 = 7 / 2; r1 = 7 / 2; r2 = 7.5 - 2; r3 = 7 - 2; r4 = min(-2.5, 1); r5 = max(2, 2.5)",
+            100,
+        );
+        assert_eq!(
+            *state,
+            [
+                Value::Int(3),
+                Value::Flt(3.),
+                Value::Int(5),
+                Value::Flt(5.),
+                Value::Int(-2),
+                Value::Flt(2.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn comparisons() {
+        use RegisterType::*;
+        let cases = [
+            ("1", "2", [true, false, true, false]),
+            ("2", "2", [false, false, true, true]),
+            ("2.5", "2", [false, true, false, true]),
+            ("-1", "true", [true, false, true, false]),
+            ("-2147483648", "2147483647", [true, false, true, false]),
+        ];
+        for (a, b, expected) in &cases {
+            let source = format!(
+                "r0 = {0} < {1}; r1 = {0} > {1}; r2 = {0} <= {1}; r3 = {0} >= {1}",
+                a, b
+            );
+            let (state, _) = run(&[Bool, Bool, Bool, Bool], &source, 100);
+            let results: Vec<_> = expected.iter().map(|b| Value::Bool(*b)).collect();
+            assert_eq!(*state, results[..], "{}", source);
+        }
+    }
+
+    /// Evaluate source with a budget of limit steps, returning the budget afterwards.
+    fn budget_after(source: &str, limit: usize) -> StepBudget {
+        let mut state = BoxState::new(&MemoryLayout::default());
+        let mut budget = StepBudget::new(StepLimit(limit));
+        for action in parse(source).unwrap() {
+            action.evaluate(&mut state, &mut budget);
+        }
+        budget
+    }
+
+    #[test]
+    fn infinite_loops_stop_at_the_step_limit() {
+        let (state, outputs) = run(
+            &[RegisterType::Int],
+            "while true { r0 += 1; print r0 }",
+            100,
+        );
+        // The While takes one step, then each iteration takes one for itself and one per Action.
+        assert_eq!(state[0], Value::Int(33));
+        assert_eq!(outputs.len(), 33);
+
+        let budget = budget_after("while true { r0 += 1 }", 100);
+        assert!(budget.is_exhausted());
+        assert!(budget.is_exceeded());
+    }
+
+    #[test]
+    fn exhausted_and_exceeded() {
+        let budget = budget_after("r0 = 1; r1 = 2", 3);
+        assert!(!budget.is_exhausted());
+        assert!(!budget.is_exceeded());
+
+        let budget = budget_after("r0 = 1; r1 = 2", 2);
+        assert!(budget.is_exhausted());
+        assert!(!budget.is_exceeded());
+
+        let budget = budget_after("r0 = 1; r1 = 2", 1);
+        assert!(budget.is_exhausted());
+        assert!(budget.is_exceeded());
+
+        let budget = budget_after("repeat 2 { r0 += 1 }", 5);
+        assert!(budget.is_exhausted());
+        assert!(!budget.is_exceeded());
+    }
+
+    #[test]
+    fn repeat_counts() {
+        let count = |source| run(&[RegisterType::Int], source, 100).0[0];
+        assert_eq!(count("repeat 3 { r0 += 1 }"), Value::Int(3));
+        assert_eq!(count("repeat 2.9 { r0 += 1 }"), Value::Int(2));
+        assert_eq!(count("repeat 0 { r0 += 1 }"), Value::Int(0));
+        assert_eq!(count("repeat -3 { r0 += 1 }"), Value::Int(0));
+        assert_eq!(count("repeat -2147483648 { r0 += 1 }"), Value::Int(0));
+        assert!(!budget_after("repeat -3 { r0 += 1 }", 1).is_exceeded());
+    }
+}
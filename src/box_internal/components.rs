@@ -1,7 +1,12 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::{fmt, ops::Deref};
 
-use crate::box_internal::actions::{Action, BoxOut};
+use crate::box_internal::{
+    actions::{Action, BoxOut},
+    values::{RegisterType, Value},
+    BoxData, TickInterval,
+};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -37,46 +42,453 @@ impl Pressable {
 
 pub type ActionScript = Vec<Action>;
 
-pub type BoxState = [f32; 8];
+/// What makes a box run one of its scripts.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
+pub enum Trigger {
+    /// The button at the index was pressed.
+    Button(usize),
+    /// The tick script at the index came due.
+    Tick(usize),
+    /// The player started using the box.
+    Enter,
+    /// The player stopped using the box.
+    Exit,
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trigger::Button(i) => write!(f, "button {}", i),
+            Trigger::Tick(i) => write!(f, "tick {}", i),
+            Trigger::Enter => write!(f, "on_enter"),
+            Trigger::Exit => write!(f, "on_exit"),
+        }
+    }
+}
+
+/// A tick script, and how long it's been since it last ran.
+#[derive(Clone, PartialEq, Debug, Default)]
+struct Tick {
+    every: TickInterval,
+    script: ActionScript,
+    /// Seconds or frames (depending on every) since the script last ran.
+    since: f32,
+}
+
+/// Component with the scripts of a box that aren't run by its buttons, see BoxData.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct BoxScripts {
+    ticks: Vec<Tick>,
+    on_enter: ActionScript,
+    on_exit: ActionScript,
+}
+
+impl BoxScripts {
+    pub fn new(data: &BoxData) -> BoxScripts {
+        BoxScripts {
+            ticks: data
+                .ticks
+                .iter()
+                .map(|tick_data| Tick {
+                    every: tick_data.every,
+                    script: tick_data.script.clone(),
+                    since: 0.,
+                })
+                .collect(),
+            on_enter: data.on_enter.clone(),
+            on_exit: data.on_exit.clone(),
+        }
+    }
+
+    /// The script that trigger runs, unless it's a button's or there's nothing to run.
+    pub fn get(&self, trigger: Trigger) -> Option<&ActionScript> {
+        let script = match trigger {
+            Trigger::Button(_) => return None,
+            Trigger::Tick(i) => &self.ticks.get(i)?.script,
+            Trigger::Enter => &self.on_enter,
+            Trigger::Exit => &self.on_exit,
+        };
+        if script.is_empty() {
+            None
+        } else {
+            Some(script)
+        }
+    }
+
+    /// Advance every tick script by a frame that took delta_seconds, returning a Trigger for every
+    /// time one came due, up to TickInterval::MAX_RUNS_PER_FRAME per script.
+    ///
+    /// Runs past that limit are dropped rather than caught up on later.
+    pub fn advance(&mut self, delta_seconds: f32) -> Vec<Trigger> {
+        let mut due = Vec::new();
+        for (i, tick) in self.ticks.iter_mut().enumerate() {
+            let (elapsed, interval) = match tick.every {
+                TickInterval::Seconds(seconds) => (delta_seconds, seconds),
+                TickInterval::Frames(frames) => (1., frames as f32),
+            };
+            tick.since += elapsed;
+            let runs = (tick.since / interval).floor();
+            if runs >= 1. {
+                tick.since %= interval;
+                let runs = (runs as usize).min(TickInterval::MAX_RUNS_PER_FRAME);
+                for _ in 0..runs {
+                    due.push(Trigger::Tick(i));
+                }
+            }
+        }
+        due
+    }
+}
+
+/// Describes the registers of a box's BoxState, as declared by its BoxData.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryLayout {
+    /// Number of registers in the BoxState.
+    pub size: usize,
+    /// Types of the first registers, the rest are Flt.
+    pub types: Vec<RegisterType>,
+    /// Starting values of the first registers, the rest start at 0.
+    /// Values are converted to the type of their register.
+    pub initial: Vec<Value>,
+    /// Optional names for the first registers, for use in tools and debugging.
+    pub names: Vec<String>,
+}
+
+impl MemoryLayout {
+    pub const DEFAULT_SIZE: usize = 8;
+
+    /// The type of the register at index.
+    pub fn register_type(&self, index: usize) -> RegisterType {
+        self.types.get(index).copied().unwrap_or_default()
+    }
+
+    /// The name of the register at index, if it has one.
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.names.get(index).map(String::as_str)
+    }
+}
+
+impl Default for MemoryLayout {
+    fn default() -> Self {
+        MemoryLayout {
+            size: MemoryLayout::DEFAULT_SIZE,
+            types: Vec::new(),
+            initial: Vec::new(),
+            names: Vec::new(),
+        }
+    }
+}
+
+/// Component containing the memory of a box, a set of typed registers described by its
+/// MemoryLayout.
+///
+/// Each register keeps the type it was created with, values written to it are converted to that
+/// type.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct BoxState(Vec<Value>);
 
-#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+impl BoxState {
+    pub fn new(layout: &MemoryLayout) -> BoxState {
+        BoxState(
+            (0..layout.size)
+                .map(|i| {
+                    let initial = layout.initial.get(i).copied().unwrap_or(Value::Int(0));
+                    initial.cast(layout.register_type(i))
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether other has the same number of registers, each with the same type, so that it can
+    /// stand in for this state.
+    pub fn same_layout(&self, other: &BoxState) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(a, b)| a.register_type() == b.register_type())
+    }
+
+    /// Store value at index, converting it to the type of that register.
+    pub fn set(&mut self, index: usize, value: Value) {
+        let register = &mut self.0[index];
+        *register = value.cast(register.register_type());
+    }
+}
+
+impl Default for BoxState {
+    fn default() -> Self {
+        BoxState::new(&MemoryLayout::default())
+    }
+}
+
+impl Deref for BoxState {
+    type Target = [Value];
+
+    fn deref(&self) -> &[Value] {
+        &self.0
+    }
+}
+
+/// Component with the id of the campaign level (see campaign::LevelEntry) a box was spawned for.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LevelId(pub String);
+
+/// Component with the most steps (see actions::StepBudget) a single button press on a box may take.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StepLimit(pub usize);
+
+impl StepLimit {
+    pub const DEFAULT: StepLimit = StepLimit(10_000);
+}
+
+impl Default for StepLimit {
+    fn default() -> Self {
+        StepLimit::DEFAULT
+    }
+}
+
+/// What a player has to do to complete a box.
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum Goal {
+    /// Output the prompt in order.
+    /// Outputs before it don't matter, so the prompt can be started over at any point.
+    #[default]
+    Sequence,
+    /// Output the prompt in order, with no wrong outputs in between.
+    /// A wrong output starts the prompt over.
+    Strict,
+    /// Output the prompt in order, within this many button presses of starting it.
+    /// Tick, on_enter and on_exit scripts don't count as presses.
+    WithinPresses(usize),
+    /// Output everything in the prompt, in any order.
+    AnyOrder,
+    /// Output each different value in the prompt at least once, in any order.
+    Set,
+    /// Have the register at index hold value (see Value::equals).
+    /// The prompt is only shown to the player, and fills up all at once.
+    Register(usize, Value),
+}
+
+/// Component tracking how far a box is towards its Goal.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(default)]
 #[serde(deny_unknown_fields)]
 pub struct Progression {
     prompt: Vec<BoxOut>,
-    #[serde(skip)]
+    goal: Goal,
+    /// The outputs that count towards the goal, each of which is also in the prompt.
+    /// For Goal::Sequence and Goal::WithinPresses, this is always the longest suffix of the
+    /// outputs since the prompt was last completed that is also a prefix of the prompt.
     answer: Vec<BoxOut>,
+    /// For Goal::WithinPresses, how many presses ago each output in the answer was output.
+    ages: Vec<usize>,
+    /// Whether the register holds its value, for Goal::Register.
+    reached: bool,
+    /// The prompt's failure function (see failure_function), built the first time it's needed.
+    #[serde(skip)]
+    failure: Vec<usize>,
+}
+
+impl PartialEq for Progression {
+    fn eq(&self, other: &Progression) -> bool {
+        self.prompt == other.prompt
+            && self.goal == other.goal
+            && self.answer == other.answer
+            && self.ages == other.ages
+            && self.reached == other.reached
+    }
+}
+
+/// For each prefix of prompt, the length of the longest proper prefix of prompt that is also a
+/// suffix of it, as used by the Knuth-Morris-Pratt algorithm.
+///
+/// When the next output doesn't continue a partial answer of length n, the longest answer that
+/// could still be continued has length failure[n - 1], so the answer can fall back without
+/// re-checking outputs it has already seen.
+fn failure_function(prompt: &[BoxOut]) -> Vec<usize> {
+    let mut failure = vec![0; prompt.len()];
+    let mut matched = 0;
+    for i in 1..prompt.len() {
+        while matched > 0 && prompt[i] != prompt[matched] {
+            matched = failure[matched - 1];
+        }
+        if prompt[i] == prompt[matched] {
+            matched += 1;
+        }
+        failure[i] = matched;
+    }
+    failure
 }
 
 impl Progression {
-    pub fn new(prompt: Vec<BoxOut>) -> Progression {
+    pub fn new(prompt: Vec<BoxOut>, goal: Goal) -> Progression {
         Progression {
             prompt,
-            answer: Vec::new(),
+            goal,
+            ..Default::default()
         }
     }
 
+    fn build_failure(&mut self) {
+        if self.failure.len() != self.prompt.len() {
+            self.failure = failure_function(&self.prompt);
+        }
+    }
+
+    /// Shorten the answer to its last len outputs, which must also be a prefix of the prompt.
+    fn keep_last(&mut self, len: usize) {
+        self.answer.drain(..self.answer.len() - len);
+        self.ages.drain(..self.ages.len().saturating_sub(len));
+    }
+
     pub fn update(&mut self, output: BoxOut) {
-        self.answer.push(output);
+        match self.goal {
+            Goal::Sequence | Goal::WithinPresses(_) => {
+                self.build_failure();
+                let mut matched = self.answer.len();
+                while matched > 0 && self.prompt.get(matched) != Some(&output) {
+                    matched = self.failure[matched - 1];
+                }
+                self.keep_last(matched);
+                if self.prompt.get(matched) == Some(&output) {
+                    self.answer.push(output);
+                    if let Goal::WithinPresses(_) = self.goal {
+                        self.ages.push(0);
+                    }
+                }
+            }
+            Goal::Strict => {
+                if self.prompt.get(self.answer.len()) != Some(&output) {
+                    self.answer.clear();
+                }
+                if self.prompt.get(self.answer.len()) == Some(&output) {
+                    self.answer.push(output);
+                }
+            }
+            Goal::AnyOrder => {
+                let needed = self.prompt.iter().filter(|o| **o == output).count();
+                let have = self.answer.iter().filter(|o| **o == output).count();
+                if have < needed {
+                    self.answer.push(output);
+                }
+            }
+            Goal::Set => {
+                if self.prompt.contains(&output) && !self.answer.contains(&output) {
+                    self.answer.push(output);
+                }
+            }
+            Goal::Register(..) => (),
+        }
+    }
 
-        while !self.answer.is_empty() && !self.prompt.starts_with(self.answer.as_slice()) {
-            self.answer.remove(0);
+    /// Count a button press towards Goal::WithinPresses, once update_script has been given what
+    /// it did.
+    pub fn end_press(&mut self) {
+        if let Goal::WithinPresses(limit) = self.goal {
+            for age in self.ages.iter_mut() {
+                *age += 1;
+            }
+            // Fall back to shorter answers until one was started recently enough that the next
+            // press could still finish it in time.
+            self.build_failure();
+            while matches!(self.ages.first(), Some(age) if *age >= limit) {
+                self.keep_last(self.failure[self.answer.len() - 1]);
+            }
         }
     }
 
+    /// Start the answer over, so that meeting the goal again takes a whole new answer.
+    fn restart(&mut self) {
+        self.answer.clear();
+        self.ages.clear();
+    }
+
+    /// Update with everything output by a single run of one of the box's scripts, and the
+    /// BoxState after it, returning whether the goal was met during the run.
+    ///
+    /// Each time the prompt is completed, the answer starts over, so a completed box can be
+    /// completed again. A Goal::Register is only met when the register comes to hold its value,
+    /// not on every run it keeps holding it.
+    ///
+    /// Runs of a button's script should be followed by end_press.
+    pub fn update_script(&mut self, outputs: &[BoxOut], state: &BoxState) -> bool {
+        let was_reached = self.reached;
+        let mut completed = false;
+        for output in outputs {
+            self.update(output.clone());
+            if self.is_complete() && !matches!(self.goal, Goal::Register(..)) {
+                completed = true;
+                self.restart();
+            }
+        }
+        if let Goal::Register(index, value) = self.goal {
+            self.reached = matches!(state.get(index), Some(register) if register.equals(value));
+        }
+        completed || (self.reached && !was_reached)
+    }
+
+    /// Update with a button press, see update_script and end_press.
+    pub fn update_press(&mut self, outputs: &[BoxOut], state: &BoxState) -> bool {
+        let completed = self.update_script(outputs, state);
+        self.end_press();
+        completed
+    }
+
     pub fn progress(&self) -> usize {
-        self.answer.len()
+        match self.goal {
+            Goal::Register(..) if self.reached => self.total(),
+            _ => self.answer.len(),
+        }
     }
 
     pub fn total(&self) -> usize {
-        self.prompt.len()
+        match self.goal {
+            Goal::Set => self
+                .prompt
+                .iter()
+                .enumerate()
+                .filter(|(i, output)| !self.prompt[..*i].contains(*output))
+                .count(),
+            _ => self.prompt.len(),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.progress() >= self.total()
     }
 
     pub fn get_prompt(&self) -> Vec<BoxOut> {
         self.prompt.clone()
     }
+
+    pub fn goal(&self) -> Goal {
+        self.goal
+    }
+
+    /// Identifies this Progression among every Progression with the same prompt and goal, by the
+    /// position in the prompt of each output in the answer.
+    pub fn snapshot(&self) -> (Vec<usize>, Vec<usize>, bool) {
+        let mut positions: Vec<usize> = self
+            .answer
+            .iter()
+            .filter_map(|output| self.prompt.iter().position(|o| o == output))
+            .collect();
+        if let Goal::AnyOrder | Goal::Set = self.goal {
+            positions.sort_unstable();
+        }
+        (positions, self.ages.clone(), self.reached)
+    }
 }
 
+/// Component that marks a box whose prompt has been completed at least once.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct Completed;
+
 /// Component that implies its entity is a member of an ordered list (at index), associated with
 /// some other Entity (collector).
 /// It is the reverse of the collector Entity having a Vec\<Entity\> component, containing this
@@ -86,3 +498,261 @@ pub struct Itemized {
     pub collector: Entity,
     pub index: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::box_internal::TickData;
+
+    /// The matching Progression::update did before it used failure_function: push the output,
+    /// then drop outputs from the front until the answer is a prefix of the prompt again.
+    fn naive_update(prompt: &[BoxOut], answer: &mut Vec<BoxOut>, output: BoxOut) {
+        answer.push(output);
+        while !answer.is_empty() && !prompt.starts_with(answer) {
+            answer.remove(0);
+        }
+    }
+
+    /// A small deterministic xorshift generator, so failures can be reproduced.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    fn ints(values: &[i32]) -> Vec<BoxOut> {
+        values.iter().map(|i| BoxOut::Int(*i)).collect()
+    }
+
+    /// Prompts where parts of the prompt repeat, which is where falling back matters.
+    fn overlapping_prompts() -> Vec<Vec<BoxOut>> {
+        vec![
+            // pin_pad.box.ron
+            ints(&[1, 2, 3, 4, 5, 4, 3, 2, 1, 3, 5, 1, 4, 2]),
+            ints(&[1, 1, 1, 2]),
+            ints(&[1, 2, 1, 2, 1]),
+            ints(&[1, 2, 1, 1, 2, 1, 2]),
+            ints(&[3, 3, 3]),
+            ints(&[7]),
+        ]
+    }
+
+    /// A stream of outputs that often contains (parts of) the prompt.
+    fn output_stream(rng: &mut Rng, prompt: &[BoxOut], len: usize) -> Vec<BoxOut> {
+        let mut stream = Vec::new();
+        while stream.len() < len {
+            match rng.below(3) {
+                0 => stream.extend_from_slice(prompt),
+                1 => {
+                    let start = rng.below(prompt.len());
+                    let end = start + 1 + rng.below(prompt.len() - start);
+                    stream.extend_from_slice(&prompt[start..end]);
+                }
+                _ => stream.push(BoxOut::Int(rng.below(6) as i32)),
+            }
+        }
+        stream
+    }
+
+    #[test]
+    fn failure_function_matches_definition() {
+        for prompt in overlapping_prompts() {
+            let failure = failure_function(&prompt);
+            for (i, fallback) in failure.iter().enumerate() {
+                let prefix = &prompt[..=i];
+                let longest = (0..=i)
+                    .rev()
+                    .find(|len| prefix.ends_with(&prompt[..*len]))
+                    .unwrap();
+                assert_eq!(*fallback, longest, "{:?} at {}", prompt, i);
+            }
+        }
+    }
+
+    #[test]
+    fn update_matches_naive() {
+        let mut rng = Rng(0x5eed);
+        for prompt in overlapping_prompts() {
+            for goal in [Goal::Sequence, Goal::WithinPresses(usize::MAX)].iter() {
+                for _ in 0..200 {
+                    let mut progression = Progression::new(prompt.clone(), *goal);
+                    let mut answer = Vec::new();
+                    // Long enough to complete the prompt, and carry on matching after it.
+                    for output in output_stream(&mut rng, &prompt, 60) {
+                        naive_update(&prompt, &mut answer, output.clone());
+                        progression.update(output);
+                        assert_eq!(progression.answer, answer, "{:?}", prompt);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn update_press_restarts_like_naive() {
+        let mut rng = Rng(0xfeed);
+        let state = BoxState::default();
+        for prompt in overlapping_prompts() {
+            let mut completions = 0;
+            for _ in 0..200 {
+                let mut progression = Progression::new(prompt.clone(), Goal::Sequence);
+                let mut answer = Vec::new();
+                for output in output_stream(&mut rng, &prompt, 60) {
+                    naive_update(&prompt, &mut answer, output.clone());
+                    let naive_completed = answer == prompt;
+                    if naive_completed {
+                        answer.clear();
+                        completions += 1;
+                    }
+                    let completed = progression.update_press(&[output], &state);
+                    assert_eq!(completed, naive_completed, "{:?}", prompt);
+                    assert_eq!(progression.answer, answer, "{:?}", prompt);
+                }
+            }
+            // Otherwise nothing was checked after a completed match
+            assert!(completions > 0, "{:?}", prompt);
+        }
+    }
+
+    /// Press a box with the given goal and prompt once per element of presses, each outputting
+    /// its Ints, and return whether each press met the goal.
+    fn completions(goal: Goal, prompt: &[i32], presses: &[&[i32]]) -> Vec<bool> {
+        let mut progression = Progression::new(ints(prompt), goal);
+        let state = BoxState::default();
+        presses
+            .iter()
+            .map(|outputs| progression.update_press(&ints(outputs), &state))
+            .collect()
+    }
+
+    #[test]
+    fn strict() {
+        let prompt = [1, 2, 3];
+        assert_eq!(
+            completions(Goal::Strict, &prompt, &[&[1, 2], &[4], &[3]]),
+            [false, false, false]
+        );
+        assert_eq!(
+            completions(Goal::Strict, &prompt, &[&[1, 2, 1], &[2], &[3]]),
+            [false, false, true]
+        );
+        assert_eq!(
+            completions(Goal::Strict, &prompt, &[&[0, 1, 2, 3, 1, 2, 3]]),
+            [true]
+        );
+    }
+
+    #[test]
+    fn within_presses() {
+        let goal = Goal::WithinPresses(2);
+        let prompt = [1, 2, 3];
+        assert_eq!(completions(goal, &prompt, &[&[1], &[2, 3]]), [false, true]);
+        assert_eq!(
+            completions(goal, &prompt, &[&[1], &[2], &[3]]),
+            [false, false, false]
+        );
+        assert_eq!(
+            completions(goal, &prompt, &[&[1], &[2], &[1], &[2], &[3]]),
+            [false, false, false, false, false]
+        );
+        assert_eq!(
+            completions(goal, &prompt, &[&[1], &[2], &[], &[1, 2], &[3]]),
+            [false, false, false, false, true]
+        );
+
+        // Scripts that aren't button presses don't age the answer.
+        let mut progression = Progression::new(ints(&prompt), goal);
+        let state = BoxState::default();
+        assert!(!progression.update_press(&ints(&[1]), &state));
+        for _ in 0..5 {
+            assert!(!progression.update_script(&[], &state));
+        }
+        assert!(!progression.update_script(&ints(&[2]), &state));
+        assert!(progression.update_press(&ints(&[3]), &state));
+    }
+
+    #[test]
+    fn any_order() {
+        let prompt = [1, 2, 2];
+        assert_eq!(
+            completions(Goal::AnyOrder, &prompt, &[&[2], &[1, 1], &[7], &[2]]),
+            [false, false, false, true]
+        );
+        assert_eq!(
+            completions(Goal::AnyOrder, &prompt, &[&[1, 1, 2], &[1]]),
+            [false, false]
+        );
+    }
+
+    #[test]
+    fn set() {
+        let prompt = [1, 2, 2, 3];
+        assert_eq!(
+            completions(Goal::Set, &prompt, &[&[3, 3], &[2, 2], &[1]]),
+            [false, false, true]
+        );
+        assert_eq!(
+            completions(Goal::Set, &prompt, &[&[1, 2, 3], &[3, 2], &[1]]),
+            [true, false, true]
+        );
+    }
+
+    #[test]
+    fn register_completes_on_reaching_the_value() {
+        let mut progression = Progression::new(ints(&[3]), Goal::Register(0, Value::Int(3)));
+        let mut state = BoxState::new(&MemoryLayout {
+            size: 1,
+            types: vec![RegisterType::Int],
+            ..Default::default()
+        });
+        let completions: Vec<bool> = [1, 3, 3, 2, 3]
+            .iter()
+            .map(|value| {
+                state.set(0, Value::Int(*value));
+                progression.update_press(&ints(&[*value]), &state)
+            })
+            .collect();
+        assert_eq!(completions, [false, true, false, false, true]);
+        assert!(progression.is_complete());
+    }
+
+    #[test]
+    fn advance_ticks() {
+        let data = BoxData {
+            ticks: vec![
+                TickData {
+                    every: TickInterval::Seconds(0.5),
+                    script: Vec::new(),
+                },
+                TickData {
+                    every: TickInterval::Frames(3),
+                    script: Vec::new(),
+                },
+            ],
+            ..Default::default()
+        };
+        let mut scripts = BoxScripts::new(&data);
+        let mut runs = |delta_seconds| {
+            let due = scripts.advance(delta_seconds);
+            let count = |i| due.iter().filter(|t| **t == Trigger::Tick(i)).count();
+            (count(0), count(1))
+        };
+
+        assert_eq!(runs(0.25), (0, 0));
+        // The remainder of 0.25 seconds carries over to the next frame.
+        assert_eq!(runs(0.5), (1, 0));
+        assert_eq!(runs(0.25), (1, 1));
+        // A long frame runs as many ticks as came due, up to the cap, and drops the rest.
+        assert_eq!(runs(2.), (4, 0));
+        assert_eq!(runs(100.25), (TickInterval::MAX_RUNS_PER_FRAME, 0));
+        assert_eq!(runs(0.25), (1, 1));
+        assert_eq!(runs(0.), (0, 0));
+        assert_eq!(runs(0.), (0, 0));
+        assert_eq!(runs(0.), (0, 1));
+    }
+}
@@ -0,0 +1,318 @@
+//! Renders ActionScripts as readable pseudo-code, in the language of the `script` module.
+//!
+//! Parsing the output of `disassemble` gives back equivalent Actions, which are the same Actions
+//! except that negated literals like `Neg(I(1))` come back folded into `I(-1)`.
+//! The one exception is a non-finite Flt constant, which the language has no way to write, and
+//! which is rendered as `NaN`, `inf` or `-inf` so that it at least reads clearly.
+use crate::box_internal::actions::{Action, Val};
+use std::fmt::{self, Write};
+
+// How tightly each kind of expression binds, matching the precedence levels of the parser.
+const TERNARY: usize = 0;
+const COMPARISON: usize = 1;
+const OR: usize = 2;
+const XOR: usize = 3;
+const AND: usize = 4;
+const SHIFT: usize = 5;
+const ADDITIVE: usize = 6;
+const MULTIPLICATIVE: usize = 7;
+const UNARY: usize = 8;
+const PRIMARY: usize = 9;
+
+const INDENT: &str = "    ";
+
+/// The operands, symbol and precedence of a Val written as a binary operator.
+fn binary_op(val: &Val) -> Option<(&Val, &'static str, &Val, usize)> {
+    Some(match val {
+        Val::Not(x) => match &**x {
+            Val::Equals(a, b) => (a, "!=", b, COMPARISON),
+            _ => return None,
+        },
+        Val::Equals(a, b) => (a, "==", b, COMPARISON),
+        Val::Lt(a, b) => (a, "<", b, COMPARISON),
+        Val::Gt(a, b) => (a, ">", b, COMPARISON),
+        Val::Le(a, b) => (a, "<=", b, COMPARISON),
+        Val::Ge(a, b) => (a, ">=", b, COMPARISON),
+        Val::Or(a, b) => (a, "|", b, OR),
+        Val::Xor(a, b) => (a, "^", b, XOR),
+        Val::And(a, b) => (a, "&", b, AND),
+        Val::Shl(a, b) => (a, "<<", b, SHIFT),
+        Val::Shr(a, b) => (a, ">>", b, SHIFT),
+        Val::Add(a, b) => (a, "+", b, ADDITIVE),
+        Val::Sub(a, b) => (a, "-", b, ADDITIVE),
+        Val::Mult(a, b) => (a, "*", b, MULTIPLICATIVE),
+        Val::Div(a, b) => (a, "/", b, MULTIPLICATIVE),
+        Val::Mod(a, b) => (a, "%", b, MULTIPLICATIVE),
+        _ => return None,
+    })
+}
+
+fn precedence(val: &Val) -> usize {
+    match (binary_op(val), val) {
+        (Some((_, _, _, precedence)), _) => precedence,
+        (None, Val::If(..)) => TERNARY,
+        (None, Val::Neg(_)) | (None, Val::Not(_)) => UNARY,
+        (None, Val::I(i)) if *i < 0 => UNARY,
+        (None, Val::C(x)) if x.is_sign_negative() => UNARY,
+        _ => PRIMARY,
+    }
+}
+
+/// Write the Val, in parentheses if it binds looser than `min_precedence`.
+fn write_operand(f: &mut fmt::Formatter, val: &Val, min_precedence: usize) -> fmt::Result {
+    if precedence(val) < min_precedence {
+        write!(f, "({})", val)
+    } else {
+        write!(f, "{}", val)
+    }
+}
+
+/// Write a Flt constant as a plain decimal with a fractional part, e.g. `2.0` or `0.0000001`, since
+/// the lexer reads neither exponents nor a bare `2` as a Flt.
+fn write_flt(f: &mut fmt::Formatter, x: f32) -> fmt::Result {
+    if !x.is_finite() {
+        return write!(f, "{}", x);
+    }
+    // Display never uses an exponent, and prints the shortest digits that read back as x
+    let digits = x.to_string();
+    if digits.contains('.') {
+        write!(f, "{}", digits)
+    } else {
+        write!(f, "{}.0", digits)
+    }
+}
+
+impl fmt::Display for Val {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some((a, sym, b, precedence)) = binary_op(self) {
+            // All binary operators are left-associative
+            write_operand(f, a, precedence)?;
+            write!(f, " {} ", sym)?;
+            return write_operand(f, b, precedence + 1);
+        }
+
+        match self {
+            Val::C(x) => write_flt(f, *x),
+            Val::I(i) => write!(f, "{}", i),
+            Val::B(b) => write!(f, "{}", b),
+            Val::G(i) => write!(f, "r{}", i),
+            Val::Get(i) => write!(f, "r[{}]", i),
+            Val::Neg(x) => {
+                write!(f, "-")?;
+                write_operand(f, x, UNARY)
+            }
+            Val::Not(x) => {
+                write!(f, "!")?;
+                write_operand(f, x, UNARY)
+            }
+            Val::Abs(x) => write!(f, "abs({})", x),
+            Val::Floor(x) => write!(f, "floor({})", x),
+            Val::Round(x) => write!(f, "round({})", x),
+            Val::Min(a, b) => write!(f, "min({}, {})", a, b),
+            Val::Max(a, b) => write!(f, "max({}, {})", a, b),
+            Val::If(condition, a, b) => {
+                write_operand(f, condition, COMPARISON)?;
+                write!(f, " ? {} : {}", a, b)
+            }
+            _ => unreachable!("binary operators are written above"),
+        }
+    }
+}
+
+/// The register an Action assigns to, and the Val it assigns written as a single expression.
+fn assignment(action: &Action) -> Option<(usize, Val)> {
+    let unary = |op: fn(Box<Val>) -> Val, a: &Val| op(Box::new(a.clone()));
+    let binary = |op: fn(Box<Val>, Box<Val>) -> Val, a: &Val, b: &Val| {
+        op(Box::new(a.clone()), Box::new(b.clone()))
+    };
+
+    Some(match action {
+        Action::Set(a, i) => (*i, a.clone()),
+        Action::Add(a, b, i) => (*i, binary(Val::Add, a, b)),
+        Action::Sub(a, b, i) => (*i, binary(Val::Sub, a, b)),
+        Action::Mult(a, b, i) => (*i, binary(Val::Mult, a, b)),
+        Action::Div(a, b, i) => (*i, binary(Val::Div, a, b)),
+        Action::Mod(a, b, i) => (*i, binary(Val::Mod, a, b)),
+        Action::Neg(a, i) => (*i, unary(Val::Neg, a)),
+        Action::Abs(a, i) => (*i, unary(Val::Abs, a)),
+        Action::Min(a, b, i) => (*i, binary(Val::Min, a, b)),
+        Action::Max(a, b, i) => (*i, binary(Val::Max, a, b)),
+        Action::Floor(a, i) => (*i, unary(Val::Floor, a)),
+        Action::Round(a, i) => (*i, unary(Val::Round, a)),
+        Action::Equals(a, b, i) => (*i, binary(Val::Equals, a, b)),
+        Action::Lt(a, b, i) => (*i, binary(Val::Lt, a, b)),
+        Action::Gt(a, b, i) => (*i, binary(Val::Gt, a, b)),
+        Action::Le(a, b, i) => (*i, binary(Val::Le, a, b)),
+        Action::Ge(a, b, i) => (*i, binary(Val::Ge, a, b)),
+        Action::Not(a, i) => (*i, unary(Val::Not, a)),
+        Action::And(a, b, i) => (*i, binary(Val::And, a, b)),
+        Action::Or(a, b, i) => (*i, binary(Val::Or, a, b)),
+        Action::Xor(a, b, i) => (*i, binary(Val::Xor, a, b)),
+        Action::Shl(a, b, i) => (*i, binary(Val::Shl, a, b)),
+        Action::Shr(a, b, i) => (*i, binary(Val::Shr, a, b)),
+        Action::IfElse(c, a, b, i) => (
+            *i,
+            Val::If(
+                Box::new(c.clone()),
+                Box::new(a.clone()),
+                Box::new(b.clone()),
+            ),
+        ),
+        _ => return None,
+    })
+}
+
+/// Operators that can be written as `rN op= x` for `rN = rN op x`. `+=` is AddEq instead.
+const COMPOUND_OPS: [&str; 7] = ["-", "*", "/", "%", "&", "|", "^"];
+
+fn write_assignment(out: &mut String, i: usize, val: &Val) -> fmt::Result {
+    let compound = match binary_op(val) {
+        Some((Val::G(j), sym, b, _)) if *j == i && COMPOUND_OPS.contains(&sym) => Some((sym, b)),
+        _ => None,
+    };
+
+    match compound {
+        Some((sym, b)) => write!(out, "r{} {}= {}", i, sym, b),
+        None => write!(out, "r{} = {}", i, val),
+    }
+}
+
+fn write_block(out: &mut String, script: &[Action], depth: usize) -> fmt::Result {
+    writeln!(out, "{{")?;
+    write_script(out, script, depth + 1)?;
+    write!(out, "{}}}", INDENT.repeat(depth))
+}
+
+fn write_if(
+    out: &mut String,
+    condition: &Val,
+    if_dos: &[Action],
+    else_dos: &[Action],
+    depth: usize,
+) -> fmt::Result {
+    write!(out, "if {} ", condition)?;
+    write_block(out, if_dos, depth)?;
+    match else_dos {
+        [] => Ok(()),
+        [Action::IfElseDo(condition, if_dos, else_dos)] => {
+            write!(out, " else ")?;
+            write_if(out, condition, if_dos, else_dos, depth)
+        }
+        _ => {
+            write!(out, " else ")?;
+            write_block(out, else_dos, depth)
+        }
+    }
+}
+
+/// Write text as a string literal, escaping it the way the script module reads it.
+fn write_string(out: &mut String, text: &str) -> fmt::Result {
+    write!(out, "\"")?;
+    for c in text.chars() {
+        match c {
+            '\\' => write!(out, "\\\\")?,
+            '"' => write!(out, "\\\"")?,
+            '\n' => write!(out, "\\n")?,
+            '\t' => write!(out, "\\t")?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    write!(out, "\"")
+}
+
+fn write_script(out: &mut String, script: &[Action], depth: usize) -> fmt::Result {
+    for action in script.iter() {
+        write!(out, "{}", INDENT.repeat(depth))?;
+        if let Some((i, val)) = assignment(action) {
+            write_assignment(out, i, &val)?;
+        } else {
+            match action {
+                Action::AddEq(a, i) => write!(out, "r{} += {}", i, a)?,
+                Action::PrintInt(a) => write!(out, "print {}", a)?,
+                Action::PrintFlt(a, precision) => write!(out, "print {}, {}", a, precision)?,
+                Action::PrintStr(text) => {
+                    write!(out, "print ")?;
+                    write_string(out, text)?;
+                }
+                Action::PrintChar(a) => write!(out, "print_char {}", a)?,
+                Action::Do(dos) => {
+                    write!(out, "do ")?;
+                    write_block(out, dos, depth)?;
+                }
+                Action::IfElseDo(condition, if_dos, else_dos) => {
+                    write_if(out, condition, if_dos, else_dos, depth)?
+                }
+                Action::Repeat(count, dos) => {
+                    write!(out, "repeat {} ", count)?;
+                    write_block(out, dos, depth)?;
+                }
+                Action::While(condition, dos) => {
+                    write!(out, "while {} ", condition)?;
+                    write_block(out, dos, depth)?;
+                }
+                _ => unreachable!("assignments are written above"),
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Render the script as pseudo-code, one statement per line, with blocks indented.
+pub fn disassemble(script: &[Action]) -> String {
+    let mut out = String::new();
+    write_script(&mut out, script, 0).expect("Writing to a String can't fail");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::box_internal::{
+        loading::{LEVEL_DIR, LEVEL_EXTENSION},
+        script::parse,
+        BoxData,
+    };
+    use std::{convert::TryFrom, fs};
+
+    fn round_trip(script: &[Action]) {
+        let source = disassemble(script);
+        let parsed = parse(&source).unwrap_or_else(|e| panic!("{}\n{}", e, source));
+        assert_eq!(parsed, script, "{}", source);
+    }
+
+    #[test]
+    fn round_trip_levels() {
+        let mut levels = 0;
+        for entry in fs::read_dir(LEVEL_DIR).unwrap() {
+            let name = entry.unwrap().file_name().to_string_lossy().into_owned();
+            if !name.ends_with(LEVEL_EXTENSION) {
+                continue;
+            }
+            let data = BoxData::try_from(name.as_str()).unwrap();
+            for button_data in data.buttons.iter() {
+                round_trip(&button_data.button);
+            }
+            for tick in data.ticks.iter() {
+                round_trip(&tick.script);
+            }
+            round_trip(&data.on_enter);
+            round_trip(&data.on_exit);
+            levels += 1;
+        }
+        assert!(levels > 0, "no levels found in {}", LEVEL_DIR);
+    }
+
+    #[test]
+    fn round_trip_literals() {
+        let constants = [0., -0., 1., -2.5, 1e-7, 1.5e20, f32::MAX, f32::MIN_POSITIVE];
+        for x in constants.iter() {
+            round_trip(&[Action::Set(Val::C(*x), 0)]);
+        }
+        round_trip(&[
+            Action::Set(Val::I(i32::MIN), 0),
+            Action::Mult(Val::I(i32::MIN), Val::C(-1e-7), 1),
+            Action::PrintStr("\\\"\n\t".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,163 @@
+//! Provides loading of BoxData from RON, either through the AssetServer or directly from disk, and
+//! the errors that can occur while doing so.
+use crate::box_internal::{
+    validation::{validate, LevelProblem},
+    BoxData,
+};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    utils::BoxedFuture,
+};
+use ron::{
+    de::from_bytes,
+    error::{Error as RonError, ErrorCode},
+};
+use std::{
+    convert::TryFrom,
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+pub const LEVEL_DIR: &str = "assets/levels";
+
+/// The extension of level files, which sets them apart from other RON assets.
+pub const LEVEL_EXTENSION: &str = "box.ron";
+
+/// The ways loading a level can fail.
+#[derive(Debug)]
+pub enum LevelLoadError {
+    /// The level file couldn't be read.
+    Missing { path: PathBuf, source: io::Error },
+    /// The level file isn't valid RON.
+    Syntax {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The level file is valid RON, but doesn't describe a BoxData, e.g. it has an unknown field.
+    Schema { path: PathBuf, message: String },
+    /// The level file describes a BoxData that would misbehave at runtime.
+    Invalid {
+        path: PathBuf,
+        problems: Vec<LevelProblem>,
+    },
+}
+
+impl LevelLoadError {
+    fn from_ron(path: &Path, error: RonError) -> LevelLoadError {
+        let path = path.to_path_buf();
+        match error.code {
+            // Errors raised by serde itself (unknown fields, missing fields, unknown variants...)
+            // don't come with a position
+            ErrorCode::Message(message) => LevelLoadError::Schema { path, message },
+            code => LevelLoadError::Syntax {
+                path,
+                line: error.position.line,
+                column: error.position.col,
+                message: code.to_string(),
+            },
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            LevelLoadError::Missing { path, .. }
+            | LevelLoadError::Syntax { path, .. }
+            | LevelLoadError::Schema { path, .. }
+            | LevelLoadError::Invalid { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for LevelLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelLoadError::Missing { path, source } => {
+                write!(f, "{}: unable to read level: {}", path.display(), source)
+            }
+            LevelLoadError::Syntax {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", path.display(), line, column, message),
+            LevelLoadError::Schema { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+            LevelLoadError::Invalid { path, problems } => {
+                write!(f, "{}: ", path.display())?;
+                for (i, problem) in problems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for LevelLoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LevelLoadError::Missing { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl BoxData {
+    /// Parse and validate a BoxData from RON, using path for error messages.
+    pub fn from_ron(path: &Path, bytes: &[u8]) -> Result<BoxData, LevelLoadError> {
+        let data: BoxData = from_bytes(bytes).map_err(|e| LevelLoadError::from_ron(path, e))?;
+        let problems = validate(&data);
+        if problems.is_empty() {
+            Ok(data)
+        } else {
+            Err(LevelLoadError::Invalid {
+                path: path.to_path_buf(),
+                problems,
+            })
+        }
+    }
+}
+
+/// Synchronously loads a BoxData from a file in `assets/levels`, for tools that run without an
+/// AssetServer.
+impl TryFrom<&str> for BoxData {
+    type Error = LevelLoadError;
+
+    fn try_from(path: &str) -> Result<BoxData, LevelLoadError> {
+        let input_path = Path::new(LEVEL_DIR).join(path);
+        let bytes = std::fs::read(&input_path).map_err(|source| LevelLoadError::Missing {
+            path: input_path.clone(),
+            source,
+        })?;
+        BoxData::from_ron(&input_path, &bytes)
+    }
+}
+
+/// AssetLoader for BoxData, so levels can be loaded (and hot-reloaded) through the AssetServer.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+pub struct BoxDataLoader;
+
+impl AssetLoader for BoxDataLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let box_data = BoxData::from_ron(load_context.path(), bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(box_data));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[LEVEL_EXTENSION]
+    }
+}
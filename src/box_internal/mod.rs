@@ -6,13 +6,19 @@
 //! Instead, other states may implement this module's systems and components as needed.
 pub mod actions;
 pub mod components;
+pub mod disassembly;
+pub mod loading;
+pub mod replay;
+pub mod script;
+pub mod simulator;
+pub mod solver;
+pub mod validation;
+pub mod values;
 
 use actions::BoxOut;
-use bevy::prelude::*;
-use components::ActionScript;
-use ron::de::from_reader;
+use bevy::{prelude::*, reflect::TypeUuid};
+use components::{ActionScript, Goal, MemoryLayout, StepLimit, Trigger};
 use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, path::Path};
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct OutputEvent {
@@ -24,64 +30,187 @@ pub struct BoxCompletedEvent {
     pub box_: Entity,
 }
 
+/// Event asking for one of a box's BoxScripts to be run, see `systems::run_triggered`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct TriggerEvent {
+    pub box_: Entity,
+    pub trigger: Trigger,
+}
+
 #[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct ButtonData {
+    /// Either a list of Actions or a script string, see the `script` module.
+    #[serde(deserialize_with = "script::deserialize_script")]
     pub button: ActionScript,
     pub translation: Vec3,
 }
 
+/// How often a tick script runs.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum TickInterval {
+    Seconds(f32),
+    Frames(u32),
+}
+
+impl TickInterval {
+    /// The shortest interval in seconds, a frame at 60 frames per second, since a tick script
+    /// can't usefully run more often than once a frame.
+    pub const MIN_SECONDS: f32 = 1. / 60.;
+    /// The most times a tick script runs in a single frame, however long the frame took, so a
+    /// stall doesn't make a box run a burst of ticks.
+    pub const MAX_RUNS_PER_FRAME: usize = 10;
+}
+
+impl Default for TickInterval {
+    fn default() -> Self {
+        TickInterval::Seconds(1.)
+    }
+}
+
+/// A script that runs on its own, every interval, while the box is being used.
 #[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
+pub struct TickData {
+    pub every: TickInterval,
+    /// Either a list of Actions or a script string, see the `script` module.
+    #[serde(deserialize_with = "script::deserialize_script")]
+    pub script: ActionScript,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize, TypeUuid)]
+#[serde(deny_unknown_fields)]
+#[uuid = "8e2f3a7c-4d1b-4f6e-9a55-0c3b7d9e6f21"]
 pub struct BoxData {
     pub prompt: Vec<BoxOut>,
     pub buttons: Vec<ButtonData>,
-}
-
-impl TryFrom<&str> for BoxData {
-    type Error = ron::error::Error;
-
-    fn try_from(path: &str) -> ron::error::Result<BoxData> {
-        let input_path = Path::new("assets/levels").join(path);
-        let f = std::fs::File::open(&input_path)?;
-        from_reader(f)
-    }
+    #[serde(default)]
+    pub memory: MemoryLayout,
+    #[serde(default)]
+    pub step_limit: StepLimit,
+    #[serde(default)]
+    pub goal: Goal,
+    #[serde(default)]
+    pub ticks: Vec<TickData>,
+    /// Script run when the player starts using the box.
+    #[serde(default, deserialize_with = "script::deserialize_script")]
+    pub on_enter: ActionScript,
+    /// Script run when the player stops using the box.
+    #[serde(default, deserialize_with = "script::deserialize_script")]
+    pub on_exit: ActionScript,
 }
 
 pub mod systems {
     use crate::box_internal::{
-        components::{ActionScript, BoxState, Itemized, Pressable, Progression},
-        BoxCompletedEvent, OutputEvent,
+        actions::{run_script, Action},
+        components::{
+            ActionScript, BoxScripts, BoxState, Completed, Itemized, LevelId, Pressable,
+            Progression, StepLimit, Trigger,
+        },
+        replay::ReplayRecorder,
+        BoxCompletedEvent, OutputEvent, TriggerEvent,
     };
-    use bevy::prelude::*;
+    use bevy::{ecs::system::SystemParam, prelude::*};
+
+    /// Runs scripts on boxes, recording them if there's a ReplayRecorder, and sending an
+    /// OutputEvent for every output and a BoxCompletedEvent whenever the goal is met.
+    #[derive(SystemParam)]
+    pub struct ScriptRunner<'a> {
+        box_query: Query<
+            'a,
+            (
+                &'static mut BoxState,
+                &'static mut Progression,
+                &'static StepLimit,
+                &'static LevelId,
+            ),
+        >,
+        output_writer: EventWriter<'a, OutputEvent>,
+        completed_writer: EventWriter<'a, BoxCompletedEvent>,
+        recorder: Option<ResMut<'a, ReplayRecorder>>,
+        time: Res<'a, Time>,
+    }
+
+    impl<'a> ScriptRunner<'a> {
+        /// Run script on box_, which trigger caused.
+        ///
+        /// # Panics
+        /// Panics if box_ isn't a box.
+        pub fn run(&mut self, box_: Entity, trigger: Trigger, script: &[Action]) {
+            let (mut state, mut progression, step_limit, level_id) = self
+                .box_query
+                .get_mut(box_)
+                .expect("Script run on an entity that isn't a Box!");
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.record_start(&level_id.0, &state, &progression);
+            }
+            let outputs = run_script(script, &mut state, *step_limit);
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.record(
+                    &level_id.0,
+                    trigger,
+                    self.time.seconds_since_startup(),
+                    outputs.clone(),
+                    state.clone(),
+                );
+            }
+            for o in outputs.iter() {
+                self.output_writer.send(OutputEvent {
+                    box_,
+                    output: o.clone(),
+                });
+            }
+            let completed = progression.update_script(&outputs, &state);
+            if let Trigger::Button(_) = trigger {
+                progression.end_press();
+            }
+            if completed {
+                self.completed_writer.send(BoxCompletedEvent { box_ });
+            }
+        }
+    }
 
     pub fn update(
-        mut box_query: Query<(&mut BoxState, &mut Progression)>,
         button_query: Query<(&Pressable, &ActionScript, &Itemized), Changed<Pressable>>,
-        mut output_writer: EventWriter<OutputEvent>,
-        mut completed_writer: EventWriter<BoxCompletedEvent>,
+        mut runner: ScriptRunner,
     ) {
         for (pressable, action_script, itemized) in button_query.iter() {
             if pressable.just_unpressed() {
-                let (mut box_, mut progression) = box_query
-                    .get_mut(itemized.collector)
-                    .expect("Itemized component on button isn't pointing to a Box!");
-                for action in action_script {
-                    let out = action.evaluate(&mut box_);
-                    if let Some(o) = out {
-                        output_writer.send(OutputEvent {
-                            box_: itemized.collector,
-                            output: o.clone(),
-                        });
-                        progression.update(o.clone());
-                        if progression.progress() >= progression.total() {
-                            completed_writer.send(BoxCompletedEvent {
-                                box_: itemized.collector,
-                            });
-                        }
-                    }
-                }
+                runner.run(
+                    itemized.collector,
+                    Trigger::Button(itemized.index),
+                    action_script,
+                );
             }
         }
     }
+
+    /// Runs the BoxScripts asked for by TriggerEvents.
+    ///
+    /// Unlike update, this isn't tied to any state, so that scripts triggered while leaving a
+    /// state still run.
+    pub fn run_triggered(
+        scripts_query: Query<&BoxScripts>,
+        mut trigger_reader: EventReader<TriggerEvent>,
+        mut runner: ScriptRunner,
+    ) {
+        for event in trigger_reader.iter() {
+            if let Some(script) = scripts_query
+                .get(event.box_)
+                .ok()
+                .and_then(|scripts| scripts.get(event.trigger))
+            {
+                runner.run(event.box_, event.trigger, script);
+            }
+        }
+    }
+
+    pub fn mark_completed(
+        mut commands: Commands,
+        mut completed_reader: EventReader<BoxCompletedEvent>,
+    ) {
+        for event in completed_reader.iter() {
+            commands.entity(event.box_).insert(Completed);
+        }
+    }
 }
@@ -0,0 +1,461 @@
+//! Provides recording of button presses on boxes, and playback of those recordings through the
+//! BoxSimulator.
+//!
+//! While a ReplayRecorder resource exists, `box_internal::systems::ScriptRunner` records every
+//! script it runs (button presses, as well as tick, on_enter and on_exit scripts), and
+//! `update_recorder` appends them to the replay file.
+//! Replay files hold one ReplayRecord per line, so recording a press never rewrites the presses
+//! before it.
+//! Since boxes are deterministic, playing a Replay back should produce exactly the same outputs
+//! and BoxStates, which lets bug reports be reproduced without the game.
+use crate::box_internal::{
+    actions::BoxOut,
+    components::{BoxState, Progression, Trigger},
+    simulator::BoxSimulator,
+    values::Value,
+    BoxData,
+};
+use bevy::{log::warn, prelude::*};
+use ron::{de::from_str, ser::to_string};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    mem,
+    path::{Path, PathBuf},
+};
+
+/// A single run of one of a box's scripts (usually a button press), and what it did.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplayPress {
+    /// Id of the campaign level the pressed box belongs to.
+    pub level: String,
+    pub trigger: Trigger,
+    /// Frames and seconds since the game started, for lining the press up with other reports.
+    pub frame: u64,
+    pub seconds: f64,
+    pub outputs: Vec<BoxOut>,
+    /// The box's BoxState right after the press.
+    pub state: BoxState,
+}
+
+/// The state of a box before its first recorded press, which may not be fresh if it was restored
+/// from a save.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplayStart {
+    pub state: BoxState,
+    pub progression: Progression,
+}
+
+/// Every press in a play session, in order.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Replay {
+    /// Starting states, keyed by level id.
+    #[serde(default)]
+    pub starts: BTreeMap<String, ReplayStart>,
+    pub presses: Vec<ReplayPress>,
+}
+
+/// A single line of a replay file.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ReplayRecord {
+    /// The starting state of the box for the level with this id.
+    Start(String, ReplayStart),
+    Press(ReplayPress),
+}
+
+/// Write each record as a line of RON.
+fn write_records<W: Write>(out: &mut W, records: &[ReplayRecord]) -> ron::error::Result<()> {
+    let mut lines = String::new();
+    for record in records {
+        lines += &to_string(record)?;
+        lines.push('\n');
+    }
+    out.write_all(lines.as_bytes())?;
+    Ok(())
+}
+
+impl Replay {
+    /// Load a replay file, where errors are reported at the line they're on.
+    pub fn load<P: AsRef<Path>>(path: P) -> ron::error::Result<Replay> {
+        let f = File::open(path)?;
+        let mut replay = Replay::default();
+        for (i, line) in BufReader::new(f).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = from_str(&line).map_err(|mut e: ron::error::Error| {
+                e.position.line = i + 1;
+                e
+            })?;
+            replay.push(record);
+        }
+        Ok(replay)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> ron::error::Result<()> {
+        write_records(&mut File::create(path)?, &self.records())
+    }
+
+    /// Add a record to the Replay. Later Starts for a level replace earlier ones.
+    pub fn push(&mut self, record: ReplayRecord) {
+        match record {
+            ReplayRecord::Start(level, start) => {
+                self.starts.insert(level, start);
+            }
+            ReplayRecord::Press(press) => self.presses.push(press),
+        }
+    }
+
+    /// The records making up the Replay, with every Start before the first press.
+    pub fn records(&self) -> Vec<ReplayRecord> {
+        self.starts
+            .iter()
+            .map(|(level, start)| ReplayRecord::Start(level.clone(), start.clone()))
+            .chain(self.presses.iter().cloned().map(ReplayRecord::Press))
+            .collect()
+    }
+}
+
+/// Resource that records presses into a Replay, which is written to path.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+    frame: u64,
+    /// Records that haven't been appended to the file yet.
+    unsaved: Vec<ReplayRecord>,
+    /// Whether the file has been started, so new records are appended rather than replacing it.
+    started: bool,
+}
+
+impl ReplayRecorder {
+    pub fn new<P: Into<PathBuf>>(path: P) -> ReplayRecorder {
+        ReplayRecorder {
+            path: path.into(),
+            replay: Replay::default(),
+            frame: 0,
+            unsaved: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Record the state of a box before it's pressed, if it hasn't been pressed yet.
+    pub fn record_start(&mut self, level: &str, state: &BoxState, progression: &Progression) {
+        if !self.replay.starts.contains_key(level) {
+            let record = ReplayRecord::Start(
+                level.to_string(),
+                ReplayStart {
+                    state: state.clone(),
+                    progression: progression.clone(),
+                },
+            );
+            self.replay.push(record.clone());
+            self.unsaved.push(record);
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        level: &str,
+        trigger: Trigger,
+        seconds: f64,
+        outputs: Vec<BoxOut>,
+        state: BoxState,
+    ) {
+        let record = ReplayRecord::Press(ReplayPress {
+            level: level.to_string(),
+            trigger,
+            frame: self.frame,
+            seconds,
+            outputs,
+            state,
+        });
+        self.replay.push(record.clone());
+        self.unsaved.push(record);
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Append the unsaved records to the file, replacing whatever was there before the first
+    /// write.
+    fn write_unsaved(&mut self) -> ron::error::Result<()> {
+        let mut f = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(self.started)
+            .truncate(!self.started)
+            .open(&self.path)?;
+        self.started = true;
+        let unsaved = mem::take(&mut self.unsaved);
+        write_records(&mut f, &unsaved)
+    }
+}
+
+/// Counts frames for the ReplayRecorder, and appends the presses recorded this frame to its file,
+/// so the recording survives a crash.
+pub fn update_recorder(recorder: Option<ResMut<ReplayRecorder>>) {
+    if let Some(mut recorder) = recorder {
+        recorder.frame += 1;
+        if !recorder.unsaved.is_empty() {
+            if let Err(e) = recorder.write_unsaved() {
+                warn!(
+                    "Unable to save replay to {}: {}",
+                    recorder.path.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// The first difference found between a Replay and the BoxSimulator playing it back.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ReplayMismatch {
+    UnknownLevel {
+        press: usize,
+        level: String,
+    },
+    /// The recorded starting state doesn't fit the level's box.
+    BadStart {
+        press: usize,
+        level: String,
+    },
+    NoSuchScript {
+        press: usize,
+        trigger: Trigger,
+    },
+    Outputs {
+        press: usize,
+        recorded: Vec<BoxOut>,
+        simulated: Vec<BoxOut>,
+    },
+    State {
+        press: usize,
+        recorded: BoxState,
+        simulated: BoxState,
+    },
+}
+
+impl fmt::Display for ReplayMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayMismatch::UnknownLevel { press, level } => {
+                write!(f, "press {}: unknown level {}", press, level)
+            }
+            ReplayMismatch::BadStart { press, level } => write!(
+                f,
+                "press {}: the recorded starting state doesn't fit level {}",
+                press, level
+            ),
+            ReplayMismatch::NoSuchScript { press, trigger } => {
+                write!(f, "press {}: the box has no {} script", press, trigger)
+            }
+            ReplayMismatch::Outputs {
+                press,
+                recorded,
+                simulated,
+            } => write!(
+                f,
+                "press {}: recorded outputs {:?}, but simulated {:?}",
+                press, recorded, simulated
+            ),
+            ReplayMismatch::State {
+                press,
+                recorded,
+                simulated,
+            } => write!(
+                f,
+                "press {}: recorded state {:?}, but simulated {:?}",
+                press,
+                &recorded[..],
+                &simulated[..]
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplayMismatch {}
+
+/// Whether two floats are the same, counting NaN as the same as itself.
+fn same_flt(a: f32, b: f32) -> bool {
+    a == b || (a.is_nan() && b.is_nan())
+}
+
+fn same_outputs(a: &[BoxOut], b: &[BoxOut]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|pair| match pair {
+            (BoxOut::Flt(a, a_precision), BoxOut::Flt(b, b_precision)) => {
+                a_precision == b_precision && same_flt(*a, *b)
+            }
+            (a, b) => a == b,
+        })
+}
+
+fn same_state(a: &BoxState, b: &BoxState) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b.iter()).all(|pair| match pair {
+            (Value::Flt(a), Value::Flt(b)) => same_flt(*a, *b),
+            (a, b) => a == b,
+        })
+}
+
+/// Run every press in the Replay on a BoxSimulator for its level, checking that each press has the
+/// recorded outputs and BoxState.
+///
+/// Levels are looked up by id in levels, and each level's box starts from its ReplayStart, or
+/// fresh if there isn't one.
+pub fn play(replay: &Replay, levels: &HashMap<String, BoxData>) -> Result<(), ReplayMismatch> {
+    let mut simulators: HashMap<&str, BoxSimulator> = HashMap::new();
+
+    for (i, press) in replay.presses.iter().enumerate() {
+        let data = levels
+            .get(&press.level)
+            .ok_or_else(|| ReplayMismatch::UnknownLevel {
+                press: i,
+                level: press.level.clone(),
+            })?;
+        if !simulators.contains_key(press.level.as_str()) {
+            let mut simulator = BoxSimulator::new(data);
+            if let Some(start) = replay.starts.get(&press.level) {
+                if !simulator.state().same_layout(&start.state) {
+                    return Err(ReplayMismatch::BadStart {
+                        press: i,
+                        level: press.level.clone(),
+                    });
+                }
+                simulator.restore(start.state.clone(), start.progression.clone());
+            }
+            simulators.insert(press.level.as_str(), simulator);
+        }
+        let simulator = simulators
+            .get_mut(press.level.as_str())
+            .expect("Simulator was just inserted");
+
+        let outputs = simulator
+            .run(press.trigger)
+            .ok_or(ReplayMismatch::NoSuchScript {
+                press: i,
+                trigger: press.trigger,
+            })?;
+        if !same_outputs(&outputs, &press.outputs) {
+            return Err(ReplayMismatch::Outputs {
+                press: i,
+                recorded: press.outputs.clone(),
+                simulated: outputs,
+            });
+        }
+        if !same_state(simulator.state(), &press.state) {
+            return Err(ReplayMismatch::State {
+                press: i,
+                recorded: press.state.clone(),
+                simulated: simulator.state().clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{convert::TryFrom, fs, process};
+
+    const LEVEL: &str = "binary.box.ron";
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("black-box-{}-{}.ron", name, process::id()))
+    }
+
+    fn levels() -> HashMap<String, BoxData> {
+        let mut levels = HashMap::new();
+        levels.insert(LEVEL.to_string(), BoxData::try_from(LEVEL).unwrap());
+        levels
+    }
+
+    /// Press buttons on the level's box, recording every press to path in two writes.
+    fn record(path: &Path, buttons: &[usize]) {
+        let data = BoxData::try_from(LEVEL).unwrap();
+        let mut simulator = BoxSimulator::new(&data);
+        let mut recorder = ReplayRecorder::new(path);
+        recorder.record_start(LEVEL, simulator.state(), simulator.progression());
+        for (i, button) in buttons.iter().enumerate() {
+            let outputs = simulator.press(*button);
+            recorder.record(
+                LEVEL,
+                Trigger::Button(*button),
+                i as f64,
+                outputs,
+                simulator.state().clone(),
+            );
+            if i == buttons.len() / 2 {
+                recorder.write_unsaved().unwrap();
+            }
+        }
+        recorder.write_unsaved().unwrap();
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round-trip");
+        let buttons = [0, 1, 1, 0, 2, 1, 0];
+        record(&path, &buttons);
+        let replay = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(replay.starts.len(), 1);
+        let triggers: Vec<_> = replay.presses.iter().map(|press| press.trigger).collect();
+        let expected: Vec<_> = buttons.iter().map(|b| Trigger::Button(*b)).collect();
+        assert_eq!(triggers, expected);
+        assert_eq!(play(&replay, &levels()), Ok(()));
+    }
+
+    #[test]
+    fn tampered_outputs() {
+        let path = temp_path("tampered");
+        record(&path, &[0, 1, 2]);
+        let contents = fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+        let mut record: ReplayRecord = from_str(&lines[2]).unwrap();
+        if let ReplayRecord::Press(press) = &mut record {
+            press.outputs.push(BoxOut::Int(12345));
+        }
+        lines[2] = to_string(&record).unwrap();
+        fs::write(&path, lines.join("\n")).unwrap();
+        let replay = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        match play(&replay, &levels()) {
+            Err(ReplayMismatch::Outputs {
+                press, recorded, ..
+            }) => {
+                assert_eq!(press, 1);
+                assert_eq!(recorded.last(), Some(&BoxOut::Int(12345)));
+            }
+            result => panic!("expected an output mismatch, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn malformed_line() {
+        let path = temp_path("malformed");
+        record(&path, &[0, 1, 2]);
+        let mut contents = fs::read_to_string(&path).unwrap();
+        contents.insert(0, '\n');
+        contents += "Press((level: \"binary.box.ron\"\n";
+        fs::write(&path, contents).unwrap();
+        let error = Replay::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(error.position.line, 6);
+    }
+}
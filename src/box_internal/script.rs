@@ -0,0 +1,798 @@
+//! Provides a small text language for writing ActionScripts, which compiles to Actions and Vals.
+//!
+//! A button's script can be written as a string instead of a list of Actions, for example:
+//! ```text
+//! button: "r0 = (r0 + 1) % 2; if r0 == 0 { print r1 } else { r1 += 1 }",
+//! ```
+//!
+//! Statements (optionally separated by `;`):
+//! - `rN = expr`, and the compound assignments `+=`, `-=`, `*=`, `/=`, `%=`, `&=`, `|=`, `^=`
+//! - `print expr` to output an Int, `print expr, N` to output a Flt shown with N decimal places,
+//!   `print "text"` to output a Str, and `print_char expr` to output the character with that
+//!   code point
+//! - `if expr { ... } else if expr { ... } else { ... }`
+//! - `repeat expr { ... }` and `while expr { ... }`
+//! - `do { ... }`, which groups statements into a single Do
+//!
+//! Expressions, from loosest to tightest binding:
+//! - `cond ? a : b`
+//! - `==`, `!=`, `<`, `>`, `<=`, `>=`
+//! - `|`, then `^`, then `&`
+//! - `<<`, `>>`
+//! - `+`, `-`
+//! - `*`, `/`, `%`
+//! - unary `-` and `!`
+//! - registers `rN`, indirect registers `r[expr]`, literals (`1` is an Int, `1.0` a Flt, `true`
+//!   and `false` Bools), parentheses, and `abs(x)`, `floor(x)`, `round(x)`, `min(a, b)`,
+//!   `max(a, b)`
+//!
+//! Strings may use the escapes `\\`, `\"`, `\n` and `\t`, and `// comments` run to the end of
+//! the line.
+//!
+//! The `disassembly` module turns Actions back into this language.
+use crate::box_internal::{
+    actions::{Action, Val},
+    components::ActionScript,
+};
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserializer,
+};
+use std::{convert::TryFrom, fmt};
+
+/// An error in a script, with the (1-based) line and column in the script where it occurred.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "script error at {}:{}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Register(usize),
+    Ident(String),
+    /// Unsigned, since the minus sign is a separate token. May be one more than i32::MAX, so that
+    /// `-2147483648` can be written.
+    Int(u32),
+    Flt(f32),
+    Str(String),
+    Sym(&'static str),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Register(i) => write!(f, "`r{}`", i),
+            Token::Ident(ident) => write!(f, "`{}`", ident),
+            Token::Int(i) => write!(f, "`{}`", i),
+            Token::Flt(x) => write!(f, "`{:?}`", x),
+            Token::Str(text) => write!(f, "`{:?}`", text),
+            Token::Sym(sym) => write!(f, "`{}`", sym),
+            Token::End => write!(f, "end of script"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+/// Symbols, longest first so that e.g. `<=` isn't lexed as `<` followed by `=`.
+const SYMBOLS: [&str; 36] = [
+    "<<", ">>", "<=", ">=", "==", "!=", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "+", "-",
+    "*", "/", "%", "<", ">", "=", "!", "&", "|", "^", "?", ":", "(", ")", "{", "}", "[", "]", ",",
+    ";",
+];
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn advance(&mut self, n: usize) {
+        for _ in 0..n {
+            if self.chars[self.pos] == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// The length of the run of characters matching `pred`, starting `offset` characters ahead.
+    fn run_length(&self, offset: usize, pred: impl Fn(char) -> bool) -> usize {
+        self.chars[(self.pos + offset).min(self.chars.len())..]
+            .iter()
+            .take_while(|c| pred(**c))
+            .count()
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            message,
+        }
+    }
+
+    /// Skip whitespace and comments.
+    fn skip_trivia(&mut self) {
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some(c), _) if c.is_whitespace() => self.advance(1),
+                (Some('/'), Some('/')) => self.advance(self.run_length(0, |c| c != '\n')),
+                _ => return,
+            }
+        }
+    }
+
+    /// Lex the token starting at the current character.
+    fn token(&mut self, c: char) -> Result<Token, ParseError> {
+        let (token, len) = if c.is_ascii_digit() {
+            let mut len = self.run_length(0, |c| c.is_ascii_digit());
+            let fraction = match self.peek(len) {
+                Some('.') => self.run_length(len + 1, |c| c.is_ascii_digit()),
+                _ => 0,
+            };
+            if fraction > 0 {
+                len += 1 + fraction;
+            }
+            let text: String = self.chars[self.pos..self.pos + len].iter().collect();
+            let token = if fraction > 0 {
+                Token::Flt(
+                    text.parse()
+                        .map_err(|_| self.error(format!("invalid number `{}`", text)))?,
+                )
+            } else {
+                Token::Int(
+                    text.parse()
+                        .ok()
+                        .filter(|i| *i <= i32::MIN.unsigned_abs())
+                        .ok_or_else(|| self.error(format!("integer `{}` is out of range", text)))?,
+                )
+            };
+            (token, len)
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = self.run_length(0, |c| c.is_ascii_alphanumeric() || c == '_');
+            let text: String = self.chars[self.pos..self.pos + len].iter().collect();
+            let is_register = c == 'r' && len > 1 && text[1..].chars().all(|c| c.is_ascii_digit());
+            let token = if is_register {
+                Token::Register(
+                    text[1..]
+                        .parse()
+                        .map_err(|_| self.error(format!("register `{}` is out of range", text)))?,
+                )
+            } else {
+                Token::Ident(text)
+            };
+            (token, len)
+        } else if c == '"' {
+            let mut text = String::new();
+            let mut len = 1;
+            loop {
+                match (self.peek(len), self.peek(len + 1)) {
+                    (Some('"'), _) => break,
+                    (Some('\\'), Some(escaped)) => {
+                        text.push(match escaped {
+                            '\\' | '"' => escaped,
+                            'n' => '\n',
+                            't' => '\t',
+                            _ => return Err(self.error(format!("unknown escape `\\{}`", escaped))),
+                        });
+                        len += 2;
+                    }
+                    (Some(c), _) => {
+                        text.push(c);
+                        len += 1;
+                    }
+                    (None, _) => return Err(self.error("unterminated string".to_string())),
+                }
+            }
+            (Token::Str(text), len + 1)
+        } else {
+            let rest: String = self.chars[self.pos..].iter().take(2).collect();
+            let sym = SYMBOLS
+                .iter()
+                .find(|sym| rest.starts_with(**sym))
+                .ok_or_else(|| self.error(format!("unexpected character `{}`", c)))?;
+            (Token::Sym(sym), sym.len())
+        };
+        self.advance(len);
+        Ok(token)
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Spanned>, ParseError> {
+    let mut lexer = Lexer {
+        chars: source.chars().collect(),
+        pos: 0,
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+    loop {
+        lexer.skip_trivia();
+        let (line, column) = (lexer.line, lexer.column);
+        let token = match lexer.peek(0) {
+            Some(c) => lexer.token(c)?,
+            None => Token::End,
+        };
+        let is_end = token == Token::End;
+        tokens.push(Spanned {
+            token,
+            line,
+            column,
+        });
+        if is_end {
+            return Ok(tokens);
+        }
+    }
+}
+
+type BinaryOp = fn(Box<Val>, Box<Val>) -> Val;
+
+fn not_equals(a: Box<Val>, b: Box<Val>) -> Val {
+    Val::Not(Box::new(Val::Equals(a, b)))
+}
+
+/// Binary operators grouped by precedence, from loosest to tightest binding.
+const BINARY_OPS: [&[(&str, BinaryOp)]; 6] = [
+    &[
+        ("==", Val::Equals),
+        ("!=", not_equals),
+        ("<", Val::Lt),
+        (">", Val::Gt),
+        ("<=", Val::Le),
+        (">=", Val::Ge),
+    ],
+    &[("|", Val::Or)],
+    &[("^", Val::Xor)],
+    &[("&", Val::And)],
+    &[("<<", Val::Shl), (">>", Val::Shr)],
+    &[("+", Val::Add), ("-", Val::Sub)],
+];
+const MULTIPLICATIVE_OPS: &[(&str, BinaryOp)] =
+    &[("*", Val::Mult), ("/", Val::Div), ("%", Val::Mod)];
+
+/// Compile `rN = val` into the Action that reads most naturally, e.g. Add(a, b, N) rather than
+/// Set(Add(a, b), N), so compiled scripts look like hand-written ones.
+fn assign(val: Val, i: usize) -> Action {
+    match val {
+        Val::Add(a, b) => Action::Add(*a, *b, i),
+        Val::Sub(a, b) => Action::Sub(*a, *b, i),
+        Val::Mult(a, b) => Action::Mult(*a, *b, i),
+        Val::Div(a, b) => Action::Div(*a, *b, i),
+        Val::Mod(a, b) => Action::Mod(*a, *b, i),
+        Val::Neg(a) => Action::Neg(*a, i),
+        Val::Abs(a) => Action::Abs(*a, i),
+        Val::Min(a, b) => Action::Min(*a, *b, i),
+        Val::Max(a, b) => Action::Max(*a, *b, i),
+        Val::Floor(a) => Action::Floor(*a, i),
+        Val::Round(a) => Action::Round(*a, i),
+        Val::Equals(a, b) => Action::Equals(*a, *b, i),
+        Val::Lt(a, b) => Action::Lt(*a, *b, i),
+        Val::Gt(a, b) => Action::Gt(*a, *b, i),
+        Val::Le(a, b) => Action::Le(*a, *b, i),
+        Val::Ge(a, b) => Action::Ge(*a, *b, i),
+        Val::Not(a) => Action::Not(*a, i),
+        Val::And(a, b) => Action::And(*a, *b, i),
+        Val::Or(a, b) => Action::Or(*a, *b, i),
+        Val::Xor(a, b) => Action::Xor(*a, *b, i),
+        Val::Shl(a, b) => Action::Shl(*a, *b, i),
+        Val::Shr(a, b) => Action::Shr(*a, *b, i),
+        Val::If(a, b, c) => Action::IfElse(*a, *b, *c, i),
+        val => Action::Set(val, i),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].token
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].token.clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        let spanned = &self.tokens[self.pos];
+        ParseError {
+            line: spanned.line,
+            column: spanned.column,
+            message,
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        self.error(format!("expected {}, found {}", expected, self.peek()))
+    }
+
+    /// Consume the symbol if it's next, returning whether it was.
+    fn eat(&mut self, sym: &str) -> bool {
+        if matches!(self.peek(), Token::Sym(s) if *s == sym) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, sym: &str) -> Result<(), ParseError> {
+        if self.eat(sym) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", sym)))
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(ident) if ident == keyword)
+    }
+
+    /// Parse statements until the closing `}` (if in a block) or the end of the script.
+    fn statements(&mut self, in_block: bool) -> Result<ActionScript, ParseError> {
+        let mut actions = Vec::new();
+        loop {
+            while self.eat(";") {}
+            match self.peek() {
+                Token::End if !in_block => return Ok(actions),
+                Token::Sym("}") if in_block => return Ok(actions),
+                Token::End => return Err(self.unexpected("`}`")),
+                _ => actions.push(self.statement()?),
+            }
+        }
+    }
+
+    fn block(&mut self) -> Result<ActionScript, ParseError> {
+        self.expect("{")?;
+        let actions = self.statements(true)?;
+        self.expect("}")?;
+        Ok(actions)
+    }
+
+    fn statement(&mut self) -> Result<Action, ParseError> {
+        if self.is_keyword("print") {
+            self.next();
+            self.print()
+        } else if self.is_keyword("print_char") {
+            self.next();
+            Ok(Action::PrintChar(self.expression()?))
+        } else if self.is_keyword("if") {
+            self.if_statement()
+        } else if self.is_keyword("do") {
+            self.next();
+            Ok(Action::Do(self.block()?))
+        } else if self.is_keyword("repeat") {
+            self.next();
+            let count = self.expression()?;
+            Ok(Action::Repeat(count, self.block()?))
+        } else if self.is_keyword("while") {
+            self.next();
+            let condition = self.expression()?;
+            Ok(Action::While(condition, self.block()?))
+        } else if let Token::Register(i) = *self.peek() {
+            self.next();
+            self.assignment(i)
+        } else {
+            Err(self.unexpected("a statement"))
+        }
+    }
+
+    fn print(&mut self) -> Result<Action, ParseError> {
+        if let Token::Str(text) = self.peek() {
+            let text = text.clone();
+            self.next();
+            return Ok(Action::PrintStr(text));
+        }
+        let val = self.expression()?;
+        if !self.eat(",") {
+            return Ok(Action::PrintInt(val));
+        }
+        match *self.peek() {
+            Token::Int(precision) => {
+                self.next();
+                Ok(Action::PrintFlt(val, precision as usize))
+            }
+            _ => Err(self.unexpected("a number of decimal places")),
+        }
+    }
+
+    fn if_statement(&mut self) -> Result<Action, ParseError> {
+        self.next();
+        let condition = self.expression()?;
+        let if_dos = self.block()?;
+        let else_dos = if self.is_keyword("else") {
+            self.next();
+            if self.is_keyword("if") {
+                vec![self.if_statement()?]
+            } else {
+                self.block()?
+            }
+        } else {
+            Vec::new()
+        };
+        Ok(Action::IfElseDo(condition, if_dos, else_dos))
+    }
+
+    fn assignment(&mut self, i: usize) -> Result<Action, ParseError> {
+        let compound: Option<BinaryOp> = match self.peek() {
+            Token::Sym("=") => None,
+            Token::Sym("+=") => {
+                self.next();
+                return Ok(Action::AddEq(self.expression()?, i));
+            }
+            Token::Sym("-=") => Some(Val::Sub),
+            Token::Sym("*=") => Some(Val::Mult),
+            Token::Sym("/=") => Some(Val::Div),
+            Token::Sym("%=") => Some(Val::Mod),
+            Token::Sym("&=") => Some(Val::And),
+            Token::Sym("|=") => Some(Val::Or),
+            Token::Sym("^=") => Some(Val::Xor),
+            _ => return Err(self.unexpected("`=`")),
+        };
+        self.next();
+        let val = self.expression()?;
+        Ok(match compound {
+            Some(op) => assign(op(Box::new(Val::G(i)), Box::new(val)), i),
+            None => assign(val, i),
+        })
+    }
+
+    fn expression(&mut self) -> Result<Val, ParseError> {
+        let condition = self.binary(0)?;
+        if self.eat("?") {
+            let a = self.expression()?;
+            self.expect(":")?;
+            let b = self.expression()?;
+            Ok(Val::If(Box::new(condition), Box::new(a), Box::new(b)))
+        } else {
+            Ok(condition)
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Val, ParseError> {
+        let ops = match BINARY_OPS.get(level) {
+            Some(ops) => *ops,
+            None => MULTIPLICATIVE_OPS,
+        };
+        let operand = |parser: &mut Parser| {
+            if level < BINARY_OPS.len() {
+                parser.binary(level + 1)
+            } else {
+                parser.unary()
+            }
+        };
+
+        let mut val = operand(self)?;
+        'outer: loop {
+            for (sym, op) in ops {
+                if self.eat(sym) {
+                    val = op(Box::new(val), Box::new(operand(self)?));
+                    continue 'outer;
+                }
+            }
+            return Ok(val);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Val, ParseError> {
+        if self.eat("-") {
+            // i32::MIN is the only Int literal that can't be negated after being parsed.
+            if *self.peek() == Token::Int(i32::MIN.unsigned_abs()) {
+                self.next();
+                return Ok(Val::I(i32::MIN));
+            }
+            Ok(match self.unary()? {
+                Val::I(i) => Val::I(i.wrapping_neg()),
+                Val::C(x) => Val::C(-x),
+                val => Val::Neg(Box::new(val)),
+            })
+        } else if self.eat("!") {
+            Ok(Val::Not(Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn arguments(&mut self, count: usize) -> Result<Vec<Val>, ParseError> {
+        self.expect("(")?;
+        let mut arguments = Vec::new();
+        for i in 0..count {
+            if i > 0 {
+                self.expect(",")?;
+            }
+            arguments.push(self.expression()?);
+        }
+        self.expect(")")?;
+        Ok(arguments)
+    }
+
+    fn primary(&mut self) -> Result<Val, ParseError> {
+        match self.peek().clone() {
+            Token::Int(i) => {
+                let i = i32::try_from(i)
+                    .map_err(|_| self.error(format!("integer `{}` is out of range", i)))?;
+                self.next();
+                Ok(Val::I(i))
+            }
+            Token::Flt(x) => {
+                self.next();
+                Ok(Val::C(x))
+            }
+            Token::Register(i) => {
+                self.next();
+                Ok(Val::G(i))
+            }
+            Token::Sym("(") => {
+                self.next();
+                let val = self.expression()?;
+                self.expect(")")?;
+                Ok(val)
+            }
+            Token::Ident(ident) => {
+                let unary: Option<fn(Box<Val>) -> Val> = match ident.as_str() {
+                    "abs" => Some(Val::Abs),
+                    "floor" => Some(Val::Floor),
+                    "round" => Some(Val::Round),
+                    _ => None,
+                };
+                let binary: Option<BinaryOp> = match ident.as_str() {
+                    "min" => Some(Val::Min),
+                    "max" => Some(Val::Max),
+                    _ => None,
+                };
+
+                if let Some(op) = unary {
+                    self.next();
+                    let a = self.arguments(1)?.remove(0);
+                    Ok(op(Box::new(a)))
+                } else if let Some(op) = binary {
+                    self.next();
+                    let mut arguments = self.arguments(2)?;
+                    let b = arguments.pop().unwrap();
+                    let a = arguments.pop().unwrap();
+                    Ok(op(Box::new(a), Box::new(b)))
+                } else {
+                    match ident.as_str() {
+                        "true" | "false" => {
+                            self.next();
+                            Ok(Val::B(ident == "true"))
+                        }
+                        "r" => {
+                            self.next();
+                            self.expect("[")?;
+                            let index = self.expression()?;
+                            self.expect("]")?;
+                            Ok(Val::Get(Box::new(index)))
+                        }
+                        _ => Err(self.unexpected("an expression")),
+                    }
+                }
+            }
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+}
+
+/// Compile a script into the equivalent Actions.
+pub fn parse(source: &str) -> Result<ActionScript, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+    };
+    parser.statements(false)
+}
+
+/// Deserialize an ActionScript from either a list of Actions or a script string.
+///
+/// Used as `#[serde(deserialize_with = ...)]` on fields containing an ActionScript.
+pub fn deserialize_script<'de, D>(deserializer: D) -> Result<ActionScript, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ScriptVisitor;
+
+    impl<'de> Visitor<'de> for ScriptVisitor {
+        type Value = ActionScript;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a list of Actions or a script string")
+        }
+
+        fn visit_str<E: de::Error>(self, source: &str) -> Result<ActionScript, E> {
+            parse(source).map_err(E::custom)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ActionScript, A::Error> {
+            let mut actions = Vec::new();
+            while let Some(action) = seq.next_element()? {
+                actions.push(action);
+            }
+            Ok(actions)
+        }
+    }
+
+    deserializer.deserialize_any(ScriptVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn b(val: Val) -> Box<Val> {
+        Box::new(val)
+    }
+
+    /// Parse `r0 = source` and return the Action it compiles to.
+    fn assignment(source: &str) -> Action {
+        let mut actions = parse(&format!("r0 = {}", source)).unwrap();
+        assert_eq!(actions.len(), 1);
+        actions.remove(0)
+    }
+
+    #[test]
+    fn precedence() {
+        use Val::*;
+        assert_eq!(
+            assignment("1 + 2 * 3"),
+            Action::Add(I(1), Mult(b(I(2)), b(I(3))), 0)
+        );
+        assert_eq!(
+            assignment("(1 + 2) * 3"),
+            Action::Mult(Add(b(I(1)), b(I(2))), I(3), 0)
+        );
+        assert_eq!(
+            assignment("1 << 2 + 3"),
+            Action::Shl(I(1), Add(b(I(2)), b(I(3))), 0)
+        );
+        assert_eq!(
+            assignment("1 | 2 ^ 3 & 4"),
+            Action::Or(I(1), Xor(b(I(2)), b(And(b(I(3)), b(I(4))))), 0)
+        );
+        assert_eq!(
+            assignment("r1 | 2 == 3"),
+            Action::Equals(Or(b(G(1)), b(I(2))), I(3), 0)
+        );
+        assert_eq!(
+            assignment("-r1 * !r2"),
+            Action::Mult(Neg(b(G(1))), Not(b(G(2))), 0)
+        );
+        assert_eq!(
+            assignment("r1 < 2 ? 3 : 4 + 5"),
+            Action::IfElse(Lt(b(G(1)), b(I(2))), I(3), Add(b(I(4)), b(I(5))), 0)
+        );
+    }
+
+    #[test]
+    fn associativity() {
+        use Val::*;
+        assert_eq!(
+            assignment("1 - 2 - 3"),
+            Action::Sub(Sub(b(I(1)), b(I(2))), I(3), 0)
+        );
+        assert_eq!(
+            assignment("8 / 4 % 3"),
+            Action::Mod(Div(b(I(8)), b(I(4))), I(3), 0)
+        );
+        assert_eq!(
+            assignment("1 << 2 >> 3"),
+            Action::Shr(Shl(b(I(1)), b(I(2))), I(3), 0)
+        );
+        assert_eq!(
+            assignment("r1 ? 1 : r2 ? 2 : 3"),
+            Action::IfElse(G(1), I(1), If(b(G(2)), b(I(2)), b(I(3))), 0)
+        );
+    }
+
+    #[test]
+    fn compound_assignments() {
+        use Val::*;
+        assert_eq!(parse("r3 += 2").unwrap(), vec![Action::AddEq(I(2), 3)]);
+        assert_eq!(parse("r3 -= 2").unwrap(), vec![Action::Sub(G(3), I(2), 3)]);
+        assert_eq!(
+            parse("r3 *= 1 + 1").unwrap(),
+            vec![Action::Mult(G(3), Add(b(I(1)), b(I(1))), 3)]
+        );
+        assert_eq!(parse("r3 /= r1").unwrap(), vec![Action::Div(G(3), G(1), 3)]);
+        assert_eq!(parse("r3 %= 2").unwrap(), vec![Action::Mod(G(3), I(2), 3)]);
+        assert_eq!(parse("r3 &= 2").unwrap(), vec![Action::And(G(3), I(2), 3)]);
+        assert_eq!(parse("r3 |= 2").unwrap(), vec![Action::Or(G(3), I(2), 3)]);
+        assert_eq!(parse("r3 ^= 2").unwrap(), vec![Action::Xor(G(3), I(2), 3)]);
+    }
+
+    #[test]
+    fn int_literals() {
+        assert_eq!(assignment("2147483647"), Action::Set(Val::I(i32::MAX), 0));
+        assert_eq!(assignment("-2147483648"), Action::Set(Val::I(i32::MIN), 0));
+        assert_eq!(
+            assignment("-2147483648 * 2"),
+            Action::Mult(Val::I(i32::MIN), Val::I(2), 0)
+        );
+        assert_eq!(assignment("-(1)"), Action::Set(Val::I(-1), 0));
+
+        let error = parse("r0 = 2147483648").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 6));
+        assert!(error.message.contains("out of range"));
+        assert!(parse("r0 = -2147483649").is_err());
+        assert!(parse("r0 = 99999999999").is_err());
+        assert!(parse("print r0, 2147483648").is_ok());
+    }
+
+    #[test]
+    fn string_escapes() {
+        assert_eq!(
+            parse(r#"print "a\\b\"c\nd\te""#).unwrap(),
+            vec![Action::PrintStr("a\\b\"c\nd\te".to_string())]
+        );
+        assert_eq!(
+            parse(r#"print "// not a comment""#).unwrap(),
+            vec![Action::PrintStr("// not a comment".to_string())]
+        );
+        assert!(parse(r#"print "\q""#).is_err());
+        assert!(parse(r#"print "unterminated"#).is_err());
+    }
+
+    #[test]
+    fn error_position() {
+        let error = parse("r0 = 1\nif r0 {\n    r1 = (2 +\n        * 3)\n}").unwrap_err();
+        assert_eq!((error.line, error.column), (4, 9));
+        assert_eq!(error.message, "expected an expression, found `*`");
+
+        let error = parse("r0 = 1\n// comment\nwhile r0 {").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 11));
+    }
+
+    #[test]
+    fn deserialize_either_form() {
+        use crate::box_internal::ButtonData;
+        let string: ButtonData =
+            ron::from_str(r#"(button: "r0 = r0 + 1", translation: (0, 0, 0))"#).unwrap();
+        let list: ButtonData =
+            ron::from_str("(button: [Add(G(0), I(1), 0)], translation: (0, 0, 0))").unwrap();
+        assert_eq!(string, list);
+        assert_eq!(string.button, vec![Action::Add(Val::G(0), Val::I(1), 0)]);
+
+        let error = ron::from_str::<ButtonData>(r#"(button: "r0 = +", translation: (0, 0, 0))"#)
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("expected an expression"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Provides a headless simulator for Black Boxes.
+//!
+//! The BoxSimulator runs the same gameplay logic as the `box_internal::systems`, but without an
+//! App, entities, or any rendering, so boxes can be exercised from tools and scripts.
+use crate::box_internal::{
+    actions::{run_script, BoxOut},
+    components::{ActionScript, BoxScripts, BoxState, Progression, StepLimit, Trigger},
+    BoxData,
+};
+
+/// A Black Box that can be pressed directly, outside of the ECS.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct BoxSimulator {
+    buttons: Vec<ActionScript>,
+    scripts: BoxScripts,
+    state: BoxState,
+    progression: Progression,
+    step_limit: StepLimit,
+    completed: bool,
+}
+
+impl BoxSimulator {
+    pub fn new(data: &BoxData) -> BoxSimulator {
+        BoxSimulator {
+            buttons: data
+                .buttons
+                .iter()
+                .map(|button_data| button_data.button.clone())
+                .collect(),
+            scripts: BoxScripts::new(data),
+            state: BoxState::new(&data.memory),
+            progression: Progression::new(data.prompt.clone(), data.goal),
+            step_limit: data.step_limit,
+            completed: false,
+        }
+    }
+
+    /// Replace the box's state and progression, e.g. with ones from a save.
+    ///
+    /// # Panics
+    /// Panics if state doesn't have the same layout as the box's own state.
+    pub fn restore(&mut self, state: BoxState, progression: Progression) {
+        assert!(
+            self.state.same_layout(&state),
+            "Restored BoxState doesn't match the box's MemoryLayout"
+        );
+        self.state = state;
+        self.progression = progression;
+    }
+
+    /// Press (and release) the button at button_index, returning every BoxOut it produced.
+    ///
+    /// # Panics
+    /// Panics if there is no button at button_index.
+    pub fn press(&mut self, button_index: usize) -> Vec<BoxOut> {
+        self.run(Trigger::Button(button_index))
+            .expect("Pressed a button the box doesn't have")
+    }
+
+    /// Run the script for trigger, returning every BoxOut it produced, or None if the box doesn't
+    /// have that script (see BoxScripts::get).
+    ///
+    /// Tick scripts only run when asked to, the simulator doesn't keep time.
+    pub fn run(&mut self, trigger: Trigger) -> Option<Vec<BoxOut>> {
+        let script = match trigger {
+            Trigger::Button(i) => self.buttons.get(i)?,
+            trigger => self.scripts.get(trigger)?,
+        };
+        let outputs = run_script(script, &mut self.state, self.step_limit);
+        self.completed |= self.progression.update_script(&outputs, &self.state);
+        if let Trigger::Button(_) = trigger {
+            self.progression.end_press();
+        }
+        Some(outputs)
+    }
+
+    pub fn num_buttons(&self) -> usize {
+        self.buttons.len()
+    }
+
+    pub fn state(&self) -> &BoxState {
+        &self.state
+    }
+
+    pub fn progression(&self) -> &Progression {
+        &self.progression
+    }
+
+    pub fn progress(&self) -> usize {
+        self.progression.progress()
+    }
+
+    /// Whether the box's goal has been met at any point, even if later presses moved away from it.
+    pub fn is_complete(&self) -> bool {
+        self.completed
+    }
+}
+
+impl From<&BoxData> for BoxSimulator {
+    fn from(data: &BoxData) -> BoxSimulator {
+        BoxSimulator::new(data)
+    }
+}
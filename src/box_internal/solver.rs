@@ -0,0 +1,194 @@
+//! Provides a solver that finds the shortest sequence of button presses that completes a box.
+//!
+//! The solver does a breadth-first search over snapshots of a box's BoxState and Progression,
+//! using the same evaluation as the BoxSimulator.
+//! Only button presses are searched: tick, on_enter and on_exit scripts (see BoxScripts) depend on
+//! timing the solver doesn't know about, so they're never run.
+use crate::box_internal::{
+    actions::run_script,
+    components::{ActionScript, BoxState, Progression},
+    values::Value,
+    BoxData,
+};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+};
+
+/// Reasons the solver can fail to produce a solution.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum SolveError {
+    /// Every reachable state has been explored, and none of them complete the prompt.
+    Unsolvable,
+    /// No solution exists within the given number of presses, but longer ones might.
+    BoundExceeded(usize),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Unsolvable => write!(f, "no sequence of presses completes the prompt"),
+            SolveError::BoundExceeded(max) => {
+                write!(
+                    f,
+                    "no sequence of at most {} presses completes the prompt",
+                    max
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// The most presses the tools try before giving up on a level, enough for every shipped level.
+pub const DEFAULT_MAX_PRESSES: usize = 64;
+
+/// A snapshot of a box, hashed by the type and bits of each register and its
+/// Progression::snapshot.
+type SnapshotKey = (Vec<(u8, u32)>, (Vec<usize>, Vec<usize>, bool));
+
+fn snapshot_key(state: &BoxState, progression: &Progression) -> SnapshotKey {
+    (
+        state
+            .iter()
+            .map(|value| match value {
+                Value::Int(i) => (0, *i as u32),
+                Value::Flt(f) => (1, f.to_bits()),
+                Value::Bool(b) => (2, *b as u32),
+            })
+            .collect(),
+        progression.snapshot(),
+    )
+}
+
+struct Node {
+    state: BoxState,
+    progression: Progression,
+    /// Index of the previous node, and the button pressed to get here from it.
+    parent: Option<(usize, usize)>,
+}
+
+/// Find the shortest sequence of button indices that completes the box's prompt, pressing at
+/// most max_presses buttons.
+pub fn solve(data: &BoxData, max_presses: usize) -> Result<Vec<usize>, SolveError> {
+    let buttons: Vec<&ActionScript> = data.buttons.iter().map(|b| &b.button).collect();
+    let start = Node {
+        state: BoxState::new(&data.memory),
+        progression: Progression::new(data.prompt.clone(), data.goal),
+        parent: None,
+    };
+    if start.progression.is_complete() {
+        return Ok(Vec::new());
+    }
+
+    let mut visited = HashSet::new();
+    visited.insert(snapshot_key(&start.state, &start.progression));
+    let mut nodes = vec![start];
+    let mut queue = VecDeque::new();
+    queue.push_back((0, 0));
+    let mut bounded = false;
+
+    while let Some((node_index, depth)) = queue.pop_front() {
+        if depth >= max_presses {
+            bounded = true;
+            continue;
+        }
+        for (button_index, button) in buttons.iter().enumerate() {
+            let mut state = nodes[node_index].state.clone();
+            let mut progression = nodes[node_index].progression.clone();
+            let outputs = run_script(button, &mut state, data.step_limit);
+            let complete = progression.update_press(&outputs, &state);
+
+            let key = snapshot_key(&state, &progression);
+            if !complete && !visited.insert(key) {
+                continue;
+            }
+
+            nodes.push(Node {
+                state,
+                progression,
+                parent: Some((node_index, button_index)),
+            });
+            if complete {
+                return Ok(presses_to(&nodes, nodes.len() - 1));
+            }
+            queue.push_back((nodes.len() - 1, depth + 1));
+        }
+    }
+
+    if bounded {
+        Err(SolveError::BoundExceeded(max_presses))
+    } else {
+        Err(SolveError::Unsolvable)
+    }
+}
+
+fn presses_to(nodes: &[Node], mut node_index: usize) -> Vec<usize> {
+    let mut presses = Vec::new();
+    while let Some((parent, button_index)) = nodes[node_index].parent {
+        presses.push(button_index);
+        node_index = parent;
+    }
+    presses.reverse();
+    presses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::box_internal::loading::{LEVEL_DIR, LEVEL_EXTENSION};
+    use std::{convert::TryFrom, fs, path::Path};
+
+    fn level(ron: &str) -> BoxData {
+        BoxData::from_ron(Path::new("test.box.ron"), ron.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn every_level_is_solvable() {
+        let mut levels = 0;
+        for entry in fs::read_dir(LEVEL_DIR).unwrap() {
+            let name = entry.unwrap().file_name().to_string_lossy().into_owned();
+            if !name.ends_with(LEVEL_EXTENSION) {
+                continue;
+            }
+            let data = BoxData::try_from(name.as_str()).unwrap();
+            if let Err(e) = solve(&data, DEFAULT_MAX_PRESSES) {
+                panic!("{}: {}", name, e);
+            }
+            levels += 1;
+        }
+        assert!(levels > 0, "no levels found in {}", LEVEL_DIR);
+    }
+
+    #[test]
+    fn shortest_solution() {
+        let data = level(
+            r#"(
+                prompt: [Int(2), Int(3)],
+                buttons: [
+                    (button: "r0 -= 1; print r0", translation: (0, 0, 0)),
+                    (button: "r0 += 1; print r0", translation: (0, 0, 0)),
+                ],
+                memory: (size: 1, types: [Int]),
+            )"#,
+        );
+        assert_eq!(solve(&data, DEFAULT_MAX_PRESSES), Ok(vec![1, 1, 1]));
+        assert_eq!(solve(&data, 2), Err(SolveError::BoundExceeded(2)));
+    }
+
+    #[test]
+    fn unsolvable() {
+        let data = level(
+            r#"(
+                prompt: [Int(2)],
+                buttons: [(button: "r0 = (r0 + 1) % 2; print r0", translation: (0, 0, 0))],
+                memory: (size: 1, types: [Int]),
+            )"#,
+        );
+        assert_eq!(
+            solve(&data, DEFAULT_MAX_PRESSES),
+            Err(SolveError::Unsolvable)
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Provides checks for problems in a BoxData that would otherwise only show up at runtime.
+use crate::box_internal::{
+    components::{Goal, Trigger},
+    BoxData, TickInterval,
+};
+use std::fmt;
+
+/// A problem found in a BoxData.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum LevelProblem {
+    /// A script reads from or writes to a BoxState index that doesn't exist.
+    RegisterOutOfRange {
+        script: Trigger,
+        index: usize,
+        size: usize,
+    },
+    /// A tick script's interval is shorter than a frame (see TickInterval::MIN_SECONDS).
+    BadTickInterval { tick: usize },
+    /// The MemoryLayout has more register types than registers.
+    TooManyTypes { count: usize, size: usize },
+    /// The MemoryLayout has more initial values than registers.
+    TooManyInitialValues { count: usize, size: usize },
+    /// The MemoryLayout has more names than registers.
+    TooManyNames { count: usize, size: usize },
+    /// The prompt is empty, so the box is complete before any buttons are pressed.
+    EmptyPrompt,
+    /// The goal is for a register that doesn't exist to hold a value.
+    GoalRegisterOutOfRange { index: usize, size: usize },
+}
+
+impl fmt::Display for LevelProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelProblem::RegisterOutOfRange {
+                script,
+                index,
+                size,
+            } => write!(
+                f,
+                "{} uses register {}, but the box only has {} registers",
+                script, index, size
+            ),
+            LevelProblem::BadTickInterval { tick } => write!(
+                f,
+                "tick {} must run at most {} times a second, and at most once a frame",
+                tick,
+                (1. / TickInterval::MIN_SECONDS).round()
+            ),
+            LevelProblem::TooManyTypes { count, size } => write!(
+                f,
+                "memory has {} register types, but only {} registers",
+                count, size
+            ),
+            LevelProblem::TooManyInitialValues { count, size } => write!(
+                f,
+                "memory has {} initial values, but only {} registers",
+                count, size
+            ),
+            LevelProblem::TooManyNames { count, size } => write!(
+                f,
+                "memory has {} register names, but only {} registers",
+                count, size
+            ),
+            LevelProblem::EmptyPrompt => write!(f, "prompt is empty"),
+            LevelProblem::GoalRegisterOutOfRange { index, size } => write!(
+                f,
+                "goal uses register {}, but the box only has {} registers",
+                index, size
+            ),
+        }
+    }
+}
+
+/// Find every LevelProblem in the BoxData.
+pub fn validate(data: &BoxData) -> Vec<LevelProblem> {
+    let mut problems = Vec::new();
+    let size = data.memory.size;
+
+    if data.prompt.is_empty() {
+        problems.push(LevelProblem::EmptyPrompt);
+    }
+
+    if let Goal::Register(index, _) = data.goal {
+        if index >= size {
+            problems.push(LevelProblem::GoalRegisterOutOfRange { index, size });
+        }
+    }
+
+    if data.memory.types.len() > size {
+        problems.push(LevelProblem::TooManyTypes {
+            count: data.memory.types.len(),
+            size,
+        });
+    }
+
+    if data.memory.initial.len() > size {
+        problems.push(LevelProblem::TooManyInitialValues {
+            count: data.memory.initial.len(),
+            size,
+        });
+    }
+
+    if data.memory.names.len() > size {
+        problems.push(LevelProblem::TooManyNames {
+            count: data.memory.names.len(),
+            size,
+        });
+    }
+
+    for (tick, tick_data) in data.ticks.iter().enumerate() {
+        let long_enough = match tick_data.every {
+            TickInterval::Seconds(seconds) => {
+                seconds >= TickInterval::MIN_SECONDS && seconds.is_finite()
+            }
+            TickInterval::Frames(frames) => frames > 0,
+        };
+        if !long_enough {
+            problems.push(LevelProblem::BadTickInterval { tick });
+        }
+    }
+
+    let scripts = data
+        .buttons
+        .iter()
+        .enumerate()
+        .map(|(i, button_data)| (Trigger::Button(i), &button_data.button))
+        .chain(
+            data.ticks
+                .iter()
+                .enumerate()
+                .map(|(i, tick_data)| (Trigger::Tick(i), &tick_data.script)),
+        )
+        .chain(vec![
+            (Trigger::Enter, &data.on_enter),
+            (Trigger::Exit, &data.on_exit),
+        ]);
+    for (script, actions) in scripts {
+        let mut indices: Vec<usize> = actions
+            .iter()
+            .flat_map(|action| action.register_indices())
+            .filter(|index| *index >= size)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        problems.extend(
+            indices
+                .into_iter()
+                .map(|index| LevelProblem::RegisterOutOfRange {
+                    script,
+                    index,
+                    size,
+                }),
+        );
+    }
+
+    problems
+}
@@ -0,0 +1,477 @@
+//! Provides the typed values stored in a BoxState, and the rules for operating on them.
+//!
+//! Operations between two Ints (or Bools, which act as 0 and 1) are done with exact integer
+//! arithmetic that wraps on overflow.
+//! As soon as a Flt is involved, both sides are converted to f32 first.
+//! Dividing by zero always results in 0, so a box can't be filled with infinities.
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Shl, Shr, Sub},
+};
+
+/// Tolerance used when comparing values that involve a Flt.
+pub const FLT_EPSILON: f32 = 0.00001;
+
+/// The type of a register in a BoxState.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub enum RegisterType {
+    Int,
+    #[default]
+    Flt,
+    Bool,
+}
+
+/// A typed value, either stored in a BoxState or produced while evaluating a Val.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum Value {
+    Int(i32),
+    Flt(f32),
+    Bool(bool),
+}
+
+impl Value {
+    pub fn register_type(&self) -> RegisterType {
+        match self {
+            Value::Int(_) => RegisterType::Int,
+            Value::Flt(_) => RegisterType::Flt,
+            Value::Bool(_) => RegisterType::Bool,
+        }
+    }
+
+    /// Convert the value to the given type.
+    ///
+    /// Flts are truncated towards zero when converted to Ints, saturating at the bounds of i32
+    /// (NaN becomes 0).
+    /// Any nonzero value converts to true.
+    pub fn cast(self, register_type: RegisterType) -> Value {
+        match register_type {
+            RegisterType::Int => Value::Int(self.as_int()),
+            RegisterType::Flt => Value::Flt(self.as_flt()),
+            RegisterType::Bool => Value::Bool(self.is_truthy()),
+        }
+    }
+
+    pub fn as_int(self) -> i32 {
+        match self {
+            Value::Int(i) => i,
+            Value::Flt(f) => f as i32,
+            Value::Bool(b) => b as i32,
+        }
+    }
+
+    pub fn as_flt(self) -> f32 {
+        match self {
+            Value::Int(i) => i as f32,
+            Value::Flt(f) => f,
+            Value::Bool(b) => b as i32 as f32,
+        }
+    }
+
+    pub fn is_truthy(self) -> bool {
+        match self {
+            Value::Int(i) => i != 0,
+            Value::Flt(f) => f != 0.,
+            Value::Bool(b) => b,
+        }
+    }
+
+    fn is_flt(self) -> bool {
+        matches!(self, Value::Flt(_))
+    }
+
+    fn is_bool(self) -> bool {
+        matches!(self, Value::Bool(_))
+    }
+
+    /// Apply int_op if the value is integral, otherwise flt_op.
+    fn unary(self, int_op: impl FnOnce(i32) -> i32, flt_op: impl FnOnce(f32) -> f32) -> Value {
+        match self {
+            Value::Flt(f) => Value::Flt(flt_op(f)),
+            _ => Value::Int(int_op(self.as_int())),
+        }
+    }
+
+    /// Apply int_op if both values are integral, otherwise flt_op.
+    fn arithmetic(
+        self,
+        other: Value,
+        int_op: impl FnOnce(i32, i32) -> i32,
+        flt_op: impl FnOnce(f32, f32) -> f32,
+    ) -> Value {
+        if self.is_flt() || other.is_flt() {
+            Value::Flt(flt_op(self.as_flt(), other.as_flt()))
+        } else {
+            Value::Int(int_op(self.as_int(), other.as_int()))
+        }
+    }
+
+    /// Modulo where the result takes the sign of the divisor, so counters wrap around nicely.
+    ///
+    /// Anything modulo 0 is 0.
+    pub fn modulo(self, other: Value) -> Value {
+        self.arithmetic(
+            other,
+            |a, b| {
+                let r = if b == 0 { 0 } else { a.wrapping_rem(b) };
+                if r != 0 && (r < 0) != (b < 0) {
+                    r.wrapping_add(b)
+                } else {
+                    r
+                }
+            },
+            |a, b| {
+                let r = if b == 0. { 0. } else { a % b };
+                if r != 0. && (r < 0.) != (b < 0.) {
+                    r + b
+                } else {
+                    r
+                }
+            },
+        )
+    }
+
+    /// Exact comparison between integral values, and approximate (within FLT_EPSILON) otherwise.
+    pub fn equals(self, other: Value) -> bool {
+        if self.is_flt() || other.is_flt() {
+            (self.as_flt() - other.as_flt()).abs() < FLT_EPSILON
+        } else {
+            self.as_int() == other.as_int()
+        }
+    }
+
+    /// Order two values numerically, or None if either is NaN.
+    pub fn compare(self, other: Value) -> Option<Ordering> {
+        if self.is_flt() || other.is_flt() {
+            self.as_flt().partial_cmp(&other.as_flt())
+        } else {
+            Some(self.as_int().cmp(&other.as_int()))
+        }
+    }
+
+    pub fn abs(self) -> Value {
+        self.unary(i32::wrapping_abs, f32::abs)
+    }
+
+    pub fn min(self, other: Value) -> Value {
+        self.arithmetic(other, i32::min, f32::min)
+    }
+
+    pub fn max(self, other: Value) -> Value {
+        self.arithmetic(other, i32::max, f32::max)
+    }
+
+    /// Round a Flt down, integral values are left as Ints.
+    pub fn floor(self) -> Value {
+        self.unary(|i| i, f32::floor)
+    }
+
+    /// Round a Flt to the nearest integer (half away from zero), integral values are left as Ints.
+    pub fn round(self) -> Value {
+        self.unary(|i| i, f32::round)
+    }
+
+    /// Apply bool_op if both values are Bools, otherwise int_op on both values converted to Ints.
+    fn bitwise(
+        self,
+        other: Value,
+        int_op: impl FnOnce(i32, i32) -> i32,
+        bool_op: impl FnOnce(bool, bool) -> bool,
+    ) -> Value {
+        if self.is_bool() && other.is_bool() {
+            Value::Bool(bool_op(self.is_truthy(), other.is_truthy()))
+        } else {
+            Value::Int(int_op(self.as_int(), other.as_int()))
+        }
+    }
+
+    /// Shift by other, where shifting by a negative amount or by 32 or more results in 0.
+    fn shift(self, other: Value, op: impl FnOnce(i32, u32) -> Option<i32>) -> Value {
+        let amount = other.as_int();
+        Value::Int(if amount < 0 {
+            0
+        } else {
+            op(self.as_int(), amount as u32).unwrap_or(0)
+        })
+    }
+}
+
+impl Add for Value {
+    type Output = Value;
+
+    fn add(self, other: Value) -> Value {
+        self.arithmetic(other, i32::wrapping_add, |a, b| a + b)
+    }
+}
+
+impl Sub for Value {
+    type Output = Value;
+
+    fn sub(self, other: Value) -> Value {
+        self.arithmetic(other, i32::wrapping_sub, |a, b| a - b)
+    }
+}
+
+impl Mul for Value {
+    type Output = Value;
+
+    fn mul(self, other: Value) -> Value {
+        self.arithmetic(other, i32::wrapping_mul, |a, b| a * b)
+    }
+}
+
+/// Ints are divided truncating towards zero.
+/// Dividing anything by zero results in zero.
+impl Div for Value {
+    type Output = Value;
+
+    fn div(self, other: Value) -> Value {
+        self.arithmetic(
+            other,
+            |a, b| if b == 0 { 0 } else { a.wrapping_div(b) },
+            |a, b| if b == 0. { 0. } else { a / b },
+        )
+    }
+}
+
+impl Neg for Value {
+    type Output = Value;
+
+    fn neg(self) -> Value {
+        self.unary(i32::wrapping_neg, |f| -f)
+    }
+}
+
+/// Logical not for Bools, bitwise not for everything else.
+impl Not for Value {
+    type Output = Value;
+
+    fn not(self) -> Value {
+        match self {
+            Value::Bool(b) => Value::Bool(!b),
+            _ => Value::Int(!self.as_int()),
+        }
+    }
+}
+
+/// Logical and for Bools, bitwise and for everything else.
+impl BitAnd for Value {
+    type Output = Value;
+
+    fn bitand(self, other: Value) -> Value {
+        self.bitwise(other, |a, b| a & b, |a, b| a && b)
+    }
+}
+
+/// Logical or for Bools, bitwise or for everything else.
+impl BitOr for Value {
+    type Output = Value;
+
+    fn bitor(self, other: Value) -> Value {
+        self.bitwise(other, |a, b| a | b, |a, b| a || b)
+    }
+}
+
+/// Logical xor for Bools, bitwise xor for everything else.
+impl BitXor for Value {
+    type Output = Value;
+
+    fn bitxor(self, other: Value) -> Value {
+        self.bitwise(other, |a, b| a ^ b, |a, b| a != b)
+    }
+}
+
+impl Shl for Value {
+    type Output = Value;
+
+    fn shl(self, other: Value) -> Value {
+        self.shift(other, i32::checked_shl)
+    }
+}
+
+/// Arithmetic shift, so negative Ints stay negative.
+impl Shr for Value {
+    type Output = Value;
+
+    fn shr(self, other: Value) -> Value {
+        self.shift(other, i32::checked_shr)
+    }
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Flt(0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_modulo_takes_the_sign_of_the_divisor() {
+        let modulo = |a, b| Value::Int(a).modulo(Value::Int(b));
+        assert_eq!(modulo(7, 3), Value::Int(1));
+        assert_eq!(modulo(-7, 3), Value::Int(2));
+        assert_eq!(modulo(7, -3), Value::Int(-2));
+        assert_eq!(modulo(-7, -3), Value::Int(-1));
+        assert_eq!(modulo(6, -3), Value::Int(0));
+        assert_eq!(modulo(5, i32::MAX), Value::Int(5));
+        assert_eq!(modulo(-5, i32::MAX), Value::Int(i32::MAX - 5));
+        assert_eq!(modulo(-5, i32::MIN), Value::Int(-5));
+        assert_eq!(modulo(5, i32::MIN), Value::Int(i32::MIN + 5));
+        assert_eq!(modulo(i32::MIN, -1), Value::Int(0));
+        assert_eq!(modulo(i32::MIN, i32::MAX), Value::Int(i32::MAX - 1));
+    }
+
+    #[test]
+    fn flt_modulo_takes_the_sign_of_the_divisor() {
+        let modulo = |a, b| Value::Flt(a).modulo(Value::Flt(b));
+        assert_eq!(modulo(7.5, 2.), Value::Flt(1.5));
+        assert_eq!(modulo(-7.5, 2.), Value::Flt(0.5));
+        assert_eq!(modulo(7.5, -2.), Value::Flt(-0.5));
+        assert_eq!(modulo(1e-10, -1e30), Value::Flt(-1e30));
+        assert_eq!(Value::Int(-1).modulo(Value::Flt(4.)), Value::Flt(3.));
+    }
+
+    #[test]
+    fn modulo_by_zero() {
+        assert_eq!(Value::Int(7).modulo(Value::Int(0)), Value::Int(0));
+        assert_eq!(Value::Int(i32::MIN).modulo(Value::Int(0)), Value::Int(0));
+        assert_eq!(Value::Flt(7.5).modulo(Value::Flt(0.)), Value::Flt(0.));
+        assert_eq!(Value::Flt(7.5).modulo(Value::Flt(-0.)), Value::Flt(0.));
+        assert_eq!(Value::Int(7).modulo(Value::Bool(false)), Value::Int(0));
+    }
+
+    #[test]
+    fn int_arithmetic_wraps() {
+        let max = Value::Int(i32::MAX);
+        let min = Value::Int(i32::MIN);
+        assert_eq!(max + Value::Int(1), min);
+        assert_eq!(min - Value::Int(1), max);
+        assert_eq!(max * Value::Int(2), Value::Int(-2));
+        assert_eq!(min / Value::Int(-1), min);
+        assert_eq!(-min, min);
+        assert_eq!(min.abs(), min);
+        assert_eq!(Value::Bool(true) + Value::Bool(true), Value::Int(2));
+    }
+
+    #[test]
+    fn casts() {
+        assert_eq!(Value::Flt(2.9).cast(RegisterType::Int), Value::Int(2));
+        assert_eq!(Value::Flt(-2.9).cast(RegisterType::Int), Value::Int(-2));
+        assert_eq!(
+            Value::Flt(1e20).cast(RegisterType::Int),
+            Value::Int(i32::MAX)
+        );
+        assert_eq!(
+            Value::Flt(-1e20).cast(RegisterType::Int),
+            Value::Int(i32::MIN)
+        );
+        assert_eq!(Value::Flt(f32::NAN).cast(RegisterType::Int), Value::Int(0));
+        assert_eq!(Value::Bool(true).cast(RegisterType::Int), Value::Int(1));
+        assert_eq!(Value::Int(-3).cast(RegisterType::Flt), Value::Flt(-3.));
+        assert_eq!(Value::Bool(true).cast(RegisterType::Flt), Value::Flt(1.));
+        assert_eq!(Value::Int(-3).cast(RegisterType::Bool), Value::Bool(true));
+        assert_eq!(Value::Int(0).cast(RegisterType::Bool), Value::Bool(false));
+        assert_eq!(Value::Flt(0.1).cast(RegisterType::Bool), Value::Bool(true));
+        assert_eq!(Value::Flt(-0.).cast(RegisterType::Bool), Value::Bool(false));
+    }
+
+    #[test]
+    fn ints_stay_ints_and_flts_spread() {
+        assert_eq!(Value::Int(7) - Value::Int(2), Value::Int(5));
+        assert_eq!(Value::Int(7) - Value::Flt(2.5), Value::Flt(4.5));
+        assert_eq!(Value::Flt(7.5) - Value::Int(2), Value::Flt(5.5));
+        assert_eq!(Value::Bool(true) - Value::Int(2), Value::Int(-1));
+        assert_eq!(Value::Int(7) / Value::Int(2), Value::Int(3));
+        assert_eq!(Value::Int(-7) / Value::Int(2), Value::Int(-3));
+        assert_eq!(Value::Int(7) / Value::Flt(2.), Value::Flt(3.5));
+        assert_eq!(Value::Int(3).min(Value::Int(-4)), Value::Int(-4));
+        assert_eq!(Value::Int(3).max(Value::Int(-4)), Value::Int(3));
+        assert_eq!(Value::Int(3).min(Value::Flt(3.5)), Value::Flt(3.));
+        assert_eq!(Value::Flt(-0.5).max(Value::Int(-1)), Value::Flt(-0.5));
+        assert_eq!(Value::Bool(true).max(Value::Bool(false)), Value::Int(1));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(Value::Int(7) / Value::Int(0), Value::Int(0));
+        assert_eq!(Value::Int(i32::MIN) / Value::Int(0), Value::Int(0));
+        assert_eq!(Value::Flt(7.5) / Value::Flt(0.), Value::Flt(0.));
+        assert_eq!(Value::Flt(-7.5) / Value::Flt(-0.), Value::Flt(0.));
+        assert_eq!(Value::Int(7) / Value::Flt(0.), Value::Flt(0.));
+        assert_eq!(Value::Flt(7.5) / Value::Bool(false), Value::Flt(0.));
+    }
+
+    #[test]
+    fn comparisons() {
+        use Ordering::*;
+        assert_eq!(Value::Int(1).compare(Value::Int(2)), Some(Less));
+        assert_eq!(Value::Int(2).compare(Value::Int(2)), Some(Equal));
+        assert_eq!(Value::Flt(2.5).compare(Value::Int(2)), Some(Greater));
+        assert_eq!(Value::Bool(true).compare(Value::Int(1)), Some(Equal));
+        assert_eq!(Value::Flt(f32::NAN).compare(Value::Int(0)), None);
+        assert_eq!(
+            Value::Int(i32::MAX).compare(Value::Int(i32::MAX - 1)),
+            Some(Greater)
+        );
+        assert!(Value::Int(3).equals(Value::Flt(3.)));
+        assert!(Value::Flt(0.1 + 0.2).equals(Value::Flt(0.3)));
+        assert!(!Value::Int(i32::MAX).equals(Value::Int(i32::MAX - 1)));
+        assert!(!Value::Flt(f32::NAN).equals(Value::Flt(f32::NAN)));
+    }
+
+    #[test]
+    fn logical_ops_on_bools() {
+        let (t, f) = (Value::Bool(true), Value::Bool(false));
+        assert_eq!(!t, f);
+        assert_eq!(!f, t);
+        assert_eq!(t & f, f);
+        assert_eq!(t & t, t);
+        assert_eq!(t | f, t);
+        assert_eq!(f | f, f);
+        assert_eq!(t ^ t, f);
+        assert_eq!(t ^ f, t);
+    }
+
+    #[test]
+    fn bitwise_ops_on_numbers() {
+        assert_eq!(!Value::Int(0), Value::Int(-1));
+        assert_eq!(!Value::Flt(5.9), Value::Int(!5));
+        assert_eq!(Value::Int(0b1100) & Value::Int(0b1010), Value::Int(0b1000));
+        assert_eq!(Value::Int(0b1100) | Value::Int(0b1010), Value::Int(0b1110));
+        assert_eq!(Value::Int(0b1100) ^ Value::Int(0b1010), Value::Int(0b0110));
+        assert_eq!(Value::Int(0b1100) & Value::Bool(true), Value::Int(0));
+        assert_eq!(Value::Flt(3.5) | Value::Bool(true), Value::Int(3));
+        assert_eq!(Value::Int(-1) ^ Value::Int(i32::MIN), Value::Int(i32::MAX));
+    }
+
+    #[test]
+    fn shifts() {
+        assert_eq!(Value::Int(1) << Value::Int(4), Value::Int(16));
+        assert_eq!(Value::Int(1) << Value::Int(31), Value::Int(i32::MIN));
+        assert_eq!(Value::Int(1) << Value::Int(32), Value::Int(0));
+        assert_eq!(Value::Int(1) << Value::Int(i32::MAX), Value::Int(0));
+        assert_eq!(Value::Int(1) << Value::Int(-1), Value::Int(0));
+        assert_eq!(Value::Int(1) << Value::Int(i32::MIN), Value::Int(0));
+        assert_eq!(Value::Int(-16) >> Value::Int(2), Value::Int(-4));
+        assert_eq!(Value::Int(i32::MIN) >> Value::Int(31), Value::Int(-1));
+        assert_eq!(Value::Int(-16) >> Value::Int(32), Value::Int(0));
+        assert_eq!(Value::Int(16) >> Value::Int(-2), Value::Int(0));
+        assert_eq!(Value::Flt(2.9) << Value::Flt(1.9), Value::Int(4));
+    }
+
+    #[test]
+    fn floor_and_round() {
+        assert_eq!(Value::Flt(2.5).floor(), Value::Flt(2.));
+        assert_eq!(Value::Flt(-2.5).floor(), Value::Flt(-3.));
+        assert_eq!(Value::Flt(2.5).round(), Value::Flt(3.));
+        assert_eq!(Value::Flt(-2.5).round(), Value::Flt(-3.));
+        assert_eq!(Value::Flt(2.49).round(), Value::Flt(2.));
+        assert_eq!(Value::Int(-7).floor(), Value::Int(-7));
+        assert_eq!(Value::Int(i32::MAX).round(), Value::Int(i32::MAX));
+        assert_eq!(Value::Bool(true).floor(), Value::Int(1));
+    }
+}
@@ -0,0 +1,86 @@
+//! Provides the Campaign resource, the ordered list of levels that make up the game.
+//!
+//! The campaign is described by `assets/campaign.ron`, so levels can be added, removed or
+//! reordered without recompiling.
+//! It also describes the intended order of the levels: a level's box is locked until the levels
+//! it requires are completed, and props (like doors) appear or disappear as levels are completed.
+use bevy::prelude::*;
+use ron::de::from_reader;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const CAMPAIGN_PATH: &str = "assets/campaign.ron";
+
+/// A single level in the Campaign.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LevelEntry {
+    /// Unique name for the level, used to refer to it from other levels.
+    pub id: String,
+    pub title: String,
+    /// File name of the level's BoxData, relative to `assets/levels`.
+    pub path: String,
+    /// Where to place the level's box in the world.
+    /// If not provided, boxes are lined up along the x axis in campaign order.
+    #[serde(default)]
+    pub position: Option<Vec3>,
+    /// Ids of the levels that must be completed before this one is unlocked.
+    #[serde(default)]
+    pub requires: Vec<String>,
+}
+
+/// What happens to a prop once the levels it requires are completed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub enum UnlockEffect {
+    /// The prop disappears, like a door opening.
+    #[default]
+    Remove,
+    /// The prop appears.
+    Spawn,
+}
+
+/// A solid block in the world, like a door or a bridge, gated by completing levels.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PropEntry {
+    /// Where the center of the prop is.
+    pub position: Vec3,
+    /// Full width, height and depth of the prop.
+    pub size: Vec3,
+    /// Ids of the levels that must be completed for effect to happen.
+    pub requires: Vec<String>,
+    #[serde(default)]
+    pub effect: UnlockEffect,
+}
+
+/// What happens to a box once it's been completed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub enum CompletedPolicy {
+    /// The box can still be entered and played with.
+    #[default]
+    Replayable,
+    /// The box can no longer be entered.
+    Locked,
+}
+
+/// Resource containing every level of the game, in order.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Campaign {
+    pub levels: Vec<LevelEntry>,
+    #[serde(default)]
+    pub props: Vec<PropEntry>,
+    #[serde(default)]
+    pub completed_boxes: CompletedPolicy,
+}
+
+impl Campaign {
+    pub fn load<P: AsRef<Path>>(path: P) -> ron::error::Result<Campaign> {
+        let f = std::fs::File::open(path)?;
+        from_reader(f)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&LevelEntry> {
+        self.levels.iter().find(|level| level.id == id)
+    }
+}
@@ -0,0 +1,202 @@
+//! Provides the window settings, read from `config/display_config.ron`.
+//!
+//! Every setting is optional, and falls back to its Default.
+//! The window is created from these settings when the DefaultPlugins are added, so the
+//! DisplayConfig has to be loaded (and turned into a WindowDescriptor) before then.
+use bevy::{prelude::*, window::WindowMode as BevyWindowMode};
+use ron::{de::from_reader, error::Error as RonError};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt, fs::File, path::Path};
+
+pub const DISPLAY_CONFIG_PATH: &str = "config/display_config.ron";
+
+/// MSAA sample counts that wgpu supports.
+pub const MSAA_SAMPLES: [u32; 2] = [1, 4];
+
+/// Whether the game runs in a window or takes up the whole screen.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    /// A window without decorations, covering the whole screen.
+    Borderless,
+    /// Exclusive fullscreen, at the video mode closest to the resolution.
+    Fullscreen,
+}
+
+impl From<WindowMode> for BevyWindowMode {
+    fn from(mode: WindowMode) -> BevyWindowMode {
+        match mode {
+            WindowMode::Windowed => BevyWindowMode::Windowed,
+            WindowMode::Borderless => BevyWindowMode::BorderlessFullscreen,
+            WindowMode::Fullscreen => BevyWindowMode::Fullscreen { use_size: true },
+        }
+    }
+}
+
+/// Resource with the window settings, as described by `config/display_config.ron`.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayConfig {
+    pub title: String,
+    /// Width and height of the window, in logical pixels.
+    pub resolution: (f32, f32),
+    /// Whether the window starts maximized, ignoring the resolution.
+    pub maximized: bool,
+    pub mode: WindowMode,
+    pub vsync: bool,
+    /// Samples per pixel for anti-aliasing, one of MSAA_SAMPLES, where 1 turns it off.
+    pub msaa_samples: u32,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            title: "Black-box".to_string(),
+            resolution: (1280., 720.),
+            maximized: false,
+            mode: WindowMode::default(),
+            vsync: true,
+            msaa_samples: 1,
+        }
+    }
+}
+
+/// A setting in a DisplayConfig that can't be applied.
+#[derive(Clone, PartialEq, Debug)]
+pub enum DisplayConfigProblem {
+    /// The resolution isn't a positive size.
+    BadResolution { width: f32, height: f32 },
+    /// The MSAA sample count isn't one of MSAA_SAMPLES.
+    BadMsaaSamples(u32),
+}
+
+impl fmt::Display for DisplayConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DisplayConfigProblem::BadResolution { width, height } => {
+                write!(f, "resolution {}x{} isn't a positive size", width, height)
+            }
+            DisplayConfigProblem::BadMsaaSamples(samples) => write!(
+                f,
+                "msaa_samples is {}, but must be one of {:?}",
+                samples, MSAA_SAMPLES
+            ),
+        }
+    }
+}
+
+/// The ways loading a DisplayConfig can fail.
+#[derive(Debug)]
+pub enum DisplayConfigError {
+    /// The file couldn't be read, or doesn't describe a DisplayConfig.
+    Ron(RonError),
+    /// The file describes a DisplayConfig with settings that can't be applied.
+    Invalid(Vec<DisplayConfigProblem>),
+}
+
+impl fmt::Display for DisplayConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DisplayConfigError::Ron(e) => write!(f, "{}", e),
+            DisplayConfigError::Invalid(problems) => {
+                for (i, problem) in problems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for DisplayConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DisplayConfigError::Ron(e) => Some(e),
+            DisplayConfigError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<RonError> for DisplayConfigError {
+    fn from(e: RonError) -> Self {
+        DisplayConfigError::Ron(e)
+    }
+}
+
+impl DisplayConfig {
+    /// Load and validate the DisplayConfig at path.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<DisplayConfig, DisplayConfigError> {
+        let f = File::open(path).map_err(RonError::from)?;
+        let config: DisplayConfig = from_reader(f)?;
+        let problems = config.validate();
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(DisplayConfigError::Invalid(problems))
+        }
+    }
+
+    /// Load the DisplayConfig at path, or the default one if it can't be loaded.
+    ///
+    /// Errors are printed to stderr rather than logged, since this happens before the
+    /// DefaultPlugins (and with them, logging) are set up.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> DisplayConfig {
+        DisplayConfig::load(&path).unwrap_or_else(|e| {
+            eprintln!(
+                "Unable to load display config from {}, using the default settings: {}",
+                path.as_ref().display(),
+                e
+            );
+            DisplayConfig::default()
+        })
+    }
+
+    /// Find every DisplayConfigProblem in the settings.
+    pub fn validate(&self) -> Vec<DisplayConfigProblem> {
+        let mut problems = Vec::new();
+
+        let (width, height) = self.resolution;
+        let positive = |x: f32| x > 0. && x.is_finite();
+        if !positive(width) || !positive(height) {
+            problems.push(DisplayConfigProblem::BadResolution { width, height });
+        }
+
+        if !MSAA_SAMPLES.contains(&self.msaa_samples) {
+            problems.push(DisplayConfigProblem::BadMsaaSamples(self.msaa_samples));
+        }
+
+        problems
+    }
+
+    pub fn window_descriptor(&self) -> WindowDescriptor {
+        WindowDescriptor {
+            title: self.title.clone(),
+            width: self.resolution.0,
+            height: self.resolution.1,
+            vsync: self.vsync,
+            mode: self.mode.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn msaa(&self) -> Msaa {
+        Msaa {
+            samples: self.msaa_samples,
+        }
+    }
+}
+
+/// Maximizes the window if the DisplayConfig asks for it, which can't be done through the
+/// WindowDescriptor.
+pub fn maximize_window(config: Res<DisplayConfig>, mut windows: ResMut<Windows>) {
+    if config.maximized {
+        if let Some(window) = windows.get_primary_mut() {
+            window.set_maximized(true);
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Provides rebindable controls, read from `config/input.ron`.
+//!
+//! The config maps the names of actions (like `interact`) to the inputs that trigger them, and
+//! the names of axes (like `walk_x`) to the inputs that move them.
+//! Systems ask the InputMap resource about actions and axes by name instead of checking KeyCodes,
+//! so players can rebind the controls to suit their keyboard layout.
+//! Gamepad inputs are read from every connected gamepad.
+use bevy::{input::InputSystem, log::warn, prelude::*};
+use ron::de::from_reader;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    path::Path,
+};
+
+pub const INPUT_CONFIG_PATH: &str = "config/input.ron";
+
+/// Action that enters the box the player is looking at.
+pub const INTERACT: &str = "interact";
+/// Action that leaves the box the player is using.
+pub const LEAVE_BOX: &str = "leave_box";
+/// Axis for walking left (-1) and right (1).
+pub const WALK_X: &str = "walk_x";
+/// Axis for walking backwards (-1) and forwards (1).
+pub const WALK_Y: &str = "walk_y";
+/// Axis for looking left (-1) and right (1), on top of looking with the mouse.
+pub const LOOK_X: &str = "look_x";
+/// Axis for looking down (-1) and up (1), on top of looking with the mouse.
+pub const LOOK_Y: &str = "look_y";
+
+/// The name of the action that presses the button at index on a box, e.g. `button_0`.
+pub fn box_button(index: usize) -> String {
+    format!("button_{}", index)
+}
+
+/// A single key, mouse button or gamepad button.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
+pub enum InputSource {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+/// Inputs that have to be held together.
+pub type Chord = Vec<InputSource>;
+
+/// Alternative Chords, any of which triggers an action.
+pub type Binding = Vec<Chord>;
+
+/// Inputs that move an axis.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum AxisSource {
+    /// -1 while negative is held, 1 while positive is held, and 0 while both or neither are.
+    Buttons {
+        negative: Binding,
+        positive: Binding,
+    },
+    /// The position of a stick or trigger, flipped if invert is set.
+    GamepadAxis {
+        axis: GamepadAxisType,
+        #[serde(default)]
+        invert: bool,
+    },
+}
+
+/// The bindings for every action and axis, as described by `config/input.ron`.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputConfig {
+    /// Sources for each axis, which are added together.
+    #[serde(default)]
+    pub axes: HashMap<String, Vec<AxisSource>>,
+    #[serde(default)]
+    pub actions: HashMap<String, Binding>,
+}
+
+impl InputConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> ron::error::Result<InputConfig> {
+        let f = File::open(path)?;
+        from_reader(f)
+    }
+}
+
+impl Default for InputConfig {
+    /// The controls for a QWERTY keyboard and a gamepad, matching the `config/input.ron` shipped
+    /// with the game.
+    fn default() -> Self {
+        let key = |key_code| vec![InputSource::Key(key_code)];
+        let pad = |button_type| vec![InputSource::Gamepad(button_type)];
+        let buttons = |negative, positive| AxisSource::Buttons {
+            negative: vec![key(negative)],
+            positive: vec![key(positive)],
+        };
+        let stick = |axis| AxisSource::GamepadAxis {
+            axis,
+            invert: false,
+        };
+
+        // The face buttons South and East are left free for interact and leave_box, so entering
+        // a box doesn't press one of its buttons on release.
+        let mut actions: HashMap<String, Binding> = [
+            (KeyCode::Key1, GamepadButtonType::West),
+            (KeyCode::Key2, GamepadButtonType::North),
+            (KeyCode::Key3, GamepadButtonType::LeftTrigger),
+            (KeyCode::Key4, GamepadButtonType::RightTrigger),
+            (KeyCode::Key5, GamepadButtonType::LeftTrigger2),
+            (KeyCode::Key6, GamepadButtonType::RightTrigger2),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (key_code, button_type))| {
+            (box_button(i), vec![key(*key_code), pad(*button_type)])
+        })
+        .collect();
+        actions.insert(
+            INTERACT.to_string(),
+            vec![key(KeyCode::E), pad(GamepadButtonType::South)],
+        );
+        actions.insert(
+            LEAVE_BOX.to_string(),
+            vec![
+                key(KeyCode::W),
+                key(KeyCode::A),
+                key(KeyCode::S),
+                key(KeyCode::D),
+                pad(GamepadButtonType::East),
+            ],
+        );
+
+        let mut axes = HashMap::new();
+        axes.insert(
+            WALK_X.to_string(),
+            vec![
+                buttons(KeyCode::A, KeyCode::D),
+                stick(GamepadAxisType::LeftStickX),
+            ],
+        );
+        axes.insert(
+            WALK_Y.to_string(),
+            vec![
+                buttons(KeyCode::S, KeyCode::W),
+                stick(GamepadAxisType::LeftStickY),
+            ],
+        );
+        axes.insert(
+            LOOK_X.to_string(),
+            vec![stick(GamepadAxisType::RightStickX)],
+        );
+        axes.insert(
+            LOOK_Y.to_string(),
+            vec![stick(GamepadAxisType::RightStickY)],
+        );
+
+        InputConfig { axes, actions }
+    }
+}
+
+/// The raw input resources that InputSources are read from.
+struct RawInput<'a> {
+    keys: &'a Input<KeyCode>,
+    mouse: &'a Input<MouseButton>,
+    gamepads: &'a HashSet<Gamepad>,
+    gamepad_buttons: &'a Input<GamepadButton>,
+    gamepad_axes: &'a Axis<GamepadAxis>,
+}
+
+impl<'a> RawInput<'a> {
+    fn held(&self, source: InputSource) -> bool {
+        match source {
+            InputSource::Key(key_code) => self.keys.pressed(key_code),
+            InputSource::Mouse(mouse_button) => self.mouse.pressed(mouse_button),
+            InputSource::Gamepad(button_type) => self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .pressed(GamepadButton(*gamepad, button_type))
+            }),
+        }
+    }
+
+    fn chord_held(&self, chord: &[InputSource]) -> bool {
+        !chord.is_empty() && chord.iter().all(|source| self.held(*source))
+    }
+
+    fn binding_held(&self, binding: &[Chord]) -> bool {
+        binding.iter().any(|chord| self.chord_held(chord))
+    }
+
+    fn axis_value(&self, source: &AxisSource) -> f32 {
+        match source {
+            AxisSource::Buttons { negative, positive } => {
+                let direction =
+                    |binding: &[Chord]| if self.binding_held(binding) { 1. } else { 0. };
+                direction(positive) - direction(negative)
+            }
+            AxisSource::GamepadAxis { axis, invert } => {
+                let value: f32 = self
+                    .gamepads
+                    .iter()
+                    .filter_map(|gamepad| self.gamepad_axes.get(GamepadAxis(*gamepad, *axis)))
+                    .sum();
+                if *invert {
+                    -value
+                } else {
+                    value
+                }
+            }
+        }
+    }
+}
+
+/// Resource with the InputConfig, and the state of every action and axis in it this frame.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct InputMap {
+    config: InputConfig,
+    /// Whether each action was held last frame, and whether it's held this frame.
+    actions: HashMap<String, (bool, bool)>,
+    axes: HashMap<String, f32>,
+    /// The gamepads that are connected, which gamepad inputs are read from.
+    gamepads: HashSet<Gamepad>,
+}
+
+impl InputMap {
+    pub fn new(config: InputConfig) -> InputMap {
+        InputMap {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Load the InputConfig at path.
+    ///
+    /// If it can't be read, a warning is logged and the default controls are used instead.
+    pub fn load<P: AsRef<Path>>(path: P) -> InputMap {
+        let config = InputConfig::load(&path).unwrap_or_else(|e| {
+            warn!(
+                "Unable to load input config from {}, using the default controls: {}",
+                path.as_ref().display(),
+                e
+            );
+            InputConfig::default()
+        });
+        InputMap::new(config)
+    }
+
+    fn connect(&mut self, event: &GamepadEvent) {
+        match event {
+            GamepadEvent(gamepad, GamepadEventType::Connected) => {
+                self.gamepads.insert(*gamepad);
+            }
+            GamepadEvent(gamepad, GamepadEventType::Disconnected) => {
+                self.gamepads.remove(gamepad);
+            }
+            _ => (),
+        }
+    }
+
+    fn update(&mut self, input: &RawInput) {
+        for (action, binding) in self.config.actions.iter() {
+            let held = input.binding_held(binding);
+            let state = self.actions.entry(action.clone()).or_default();
+            *state = (state.1, held);
+        }
+        for (axis, sources) in self.config.axes.iter() {
+            let value: f32 = sources.iter().map(|source| input.axis_value(source)).sum();
+            self.axes.insert(axis.clone(), value.clamp(-1., 1.));
+        }
+    }
+
+    fn action(&self, action: &str) -> (bool, bool) {
+        self.actions.get(action).copied().unwrap_or_default()
+    }
+
+    /// Whether the action is held. Unbound actions never are.
+    pub fn pressed(&self, action: &str) -> bool {
+        self.action(action).1
+    }
+
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.action(action) == (false, true)
+    }
+
+    pub fn just_released(&self, action: &str) -> bool {
+        self.action(action) == (true, false)
+    }
+
+    /// The value of the axis, between -1 and 1. Unbound axes are always 0.
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.axes.get(axis).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct InputMapPlugin;
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(InputMap::load(INPUT_CONFIG_PATH))
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_input_map.system().after(InputSystem),
+            );
+    }
+}
+
+pub fn update_input_map(
+    mut input_map: ResMut<InputMap>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut gamepad_reader: EventReader<GamepadEvent>,
+) {
+    for event in gamepad_reader.iter() {
+        input_map.connect(event);
+    }
+    let gamepads = input_map.gamepads.clone();
+    input_map.update(&RawInput {
+        keys: &keys,
+        mouse: &mouse,
+        gamepads: &gamepads,
+        gamepad_buttons: &gamepad_buttons,
+        gamepad_axes: &gamepad_axes,
+    });
+}
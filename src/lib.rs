@@ -0,0 +1,42 @@
+//! # Black Box
+//! Black Box is, so far, a very simple puzzle game written with Rust + Bevy.
+//!
+//! This project uses bevy's new State Machine logic, and its modules are structured around these
+//! states.
+//! Then, they are further split into components, systems, transitions (for entering and
+//! exiting systems) and resources, as necessary.
+//! There are also such sub-modules on the top-level, for objects and systems that aren't
+//! associated with a particular state.
+//! See the sub-module documentation for more details.
+pub mod box_internal;
+pub mod campaign;
+pub mod display_config;
+pub mod input_map;
+pub mod roaming;
+pub mod save;
+pub mod standard_box;
+pub mod transitions;
+
+use bevy::prelude::*;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum AppState {
+    StandardBox,
+    Roaming,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
+enum SystemLabels {
+    // Left as an enum for potential future labels
+    InputLabel,
+}
+
+pub mod resources {
+    use bevy::prelude::*;
+
+    #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
+    pub struct ColorHandles {
+        pub white: Handle<ColorMaterial>,
+        pub green: Handle<ColorMaterial>,
+    }
+}
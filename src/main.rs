@@ -1,73 +1,63 @@
-//! # Black Box
-//! Black Box is, so far, a very simple puzzle game written with Rust + Bevy.
-//!
-//! This project uses bevy's new State Machine logic, and its modules are structured around these
-//! states.
-//! Then, they are further split into components, systems, transitions (for entering and
-//! exiting systems) and resources, as necessary.
-//! There are also such sub-modules on the top-level, for objects and systems that aren't
-//! associated with a particular state.
-//! See the sub-module documentation for more details.
-pub mod box_internal;
-pub mod roaming;
-pub mod standard_box;
-pub mod transitions;
-
+//! The Black Box game binary.
+//! See the library documentation for an overview of the modules.
 use bevy::prelude::*;
+use black_box::{
+    box_internal,
+    campaign::{Campaign, CAMPAIGN_PATH},
+    display_config::{self, DisplayConfig, DISPLAY_CONFIG_PATH},
+    input_map, roaming,
+    save::{self, SaveFile},
+    standard_box, transitions, AppState,
+};
 use heron::prelude::*;
-use std::num::ParseIntError;
+use std::{env, num::ParseIntError};
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
-pub enum AppState {
-    StandardBox,
-    Roaming,
-}
-
-pub const LEVEL_ORDER: [&str; 10] = [
-    "pin_pad.ron",
-    "counter.ron",
-    "mod_counter.ron",
-    "dec_inc.ron",
-    "inc_dec.ron",
-    "two_toggles.ron",
-    "toggle_neg_pos.ron",
-    "toggle_negout_pos.ron",
-    "toggle_rot.ron",
-    "binary.ron",
-];
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
-enum SystemLabels {
-    // Left as an enum for potential future labels
-    InputLabel,
+/// The value given after flag on the command line, e.g. the path in `--record PATH`.
+fn flag_value(flag: &str) -> Option<String> {
+    let mut args = env::args().skip_while(|arg| arg != flag);
+    args.next()?;
+    args.next()
 }
 
 fn main() -> Result<(), ParseIntError> {
-    App::build()
+    let display_config = DisplayConfig::load_or_default(DISPLAY_CONFIG_PATH);
+
+    let mut app = App::build();
+    app.insert_resource(display_config.window_descriptor())
+        .insert_resource(display_config.msaa())
+        .insert_resource(display_config)
         .add_plugins(DefaultPlugins)
         .add_plugin(PhysicsPlugin::default())
         .add_plugin(roaming::RaycastingPluginNoDebug::<
             roaming::components::BoxRayCastSet,
         >::default())
-        .insert_resource(Msaa { samples: 1 })
+        .insert_resource(Campaign::load(CAMPAIGN_PATH).expect("Unable to load campaign"))
+        .insert_resource(SaveFile::load(
+            flag_value("--save").map_or_else(save::default_save_path, Into::into),
+        ))
         .add_state(AppState::Roaming)
+        .add_asset::<box_internal::BoxData>()
+        .init_asset_loader::<box_internal::loading::BoxDataLoader>()
+        .add_startup_system(transitions::watch_for_changes.system())
+        .add_startup_system(display_config::maximize_window.system())
         .add_event::<box_internal::OutputEvent>()
         .add_event::<box_internal::BoxCompletedEvent>()
+        .add_event::<box_internal::TriggerEvent>()
         .add_event::<standard_box::StandardBoxEvent>()
+        .add_system(box_internal::systems::run_triggered.system())
+        .add_system(box_internal::systems::mark_completed.system())
+        .add_system(box_internal::replay::update_recorder.system())
         .add_startup_system(transitions::add_colors.system())
+        .add_plugin(input_map::InputMapPlugin)
         .add_plugin(standard_box::StandardBoxPlugin)
         .add_plugin(roaming::RoamingPlugin)
-        .run();
+        .add_plugin(save::SavePlugin);
 
-    Ok(())
-}
+    if let Some(path) = flag_value("--record") {
+        app.insert_resource(box_internal::replay::ReplayRecorder::new(path));
+    }
 
-pub mod resources {
-    use bevy::prelude::*;
+    app.run();
 
-    #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
-    pub struct ColorHandles {
-        pub white: Handle<ColorMaterial>,
-        pub green: Handle<ColorMaterial>,
-    }
+    Ok(())
 }
@@ -59,6 +59,26 @@ impl From<Tilt> for Quat {
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
 pub struct Player;
 
+/// Component that marks where a level's box belongs in the world.
+/// Its entity also has the Handle\<BoxData\> for the level, and box_ is the currently spawned box
+/// for it, if the BoxData has loaded.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+pub struct LevelSlot {
+    pub box_: Option<Entity>,
+}
+
+/// Component that marks a box that can't be entered yet, because the levels its LevelEntry
+/// requires haven't all been completed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct Locked;
+
+/// Component for a prop that is waiting on the levels required by the PropEntry at index in the
+/// Campaign.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct Prop {
+    pub index: usize,
+}
+
 /// Not a component, but rather a type for flagging the generic RayCast components/plugins
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
 pub struct BoxRayCastSet;
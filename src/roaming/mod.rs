@@ -20,6 +20,10 @@ pub mod resources {
 
     #[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
     pub struct LookSensitivity(pub f32);
+
+    /// How fast the look axes turn the camera, in radians per second.
+    #[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+    pub struct LookSpeed(pub f32);
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
@@ -29,6 +33,7 @@ impl Plugin for RoamingPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.insert_resource(resources::WalkSpeed(3.))
             .insert_resource(resources::LookSensitivity(0.06))
+            .insert_resource(resources::LookSpeed(2.5))
             .insert_resource(Gravity::from(Vec3::new(0.0, -9.81, 0.0)))
             .add_system_set(
                 SystemSet::on_enter(AppState::Roaming)
@@ -36,7 +41,8 @@ impl Plugin for RoamingPlugin {
                     .with_system(transitions::light_setup.system())
                     .with_system(transitions::floor_setup.system())
                     .with_system(transitions::grab_cursor.system())
-                    .with_system(transitions::black_box_setup.system()),
+                    .with_system(transitions::black_box_setup.system())
+                    .with_system(transitions::prop_setup.system()),
             )
             .add_system_set(
                 SystemSet::on_update(AppState::Roaming)
@@ -53,7 +59,14 @@ impl Plugin for RoamingPlugin {
                     .with_system(systems::body_turn.system())
                     .with_system(systems::camera_tilt.system())
                     .with_system(systems::box_interaction.system()),
-            );
+            )
+            // Not tied to a state, so boxes are still respawned when a level is edited while the
+            // player is using a box.
+            .add_system(transitions::spawn_loaded_boxes.system())
+            .add_system(transitions::spawn_load_failure_placeholders.system())
+            .add_system(systems::lock_boxes.system())
+            .add_system(systems::unlock_props.system())
+            .add_system(systems::render_box_status.system());
     }
 }
 
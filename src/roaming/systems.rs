@@ -1,36 +1,26 @@
 use crate::{
-    box_internal::components::BoxState,
+    box_internal::components::{BoxState, Completed, LevelId},
+    campaign::{Campaign, CompletedPolicy, UnlockEffect},
+    input_map::{InputMap, INTERACT, LOOK_X, LOOK_Y, WALK_X, WALK_Y},
     roaming::{
         components::*,
-        resources::{LookSensitivity, WalkSpeed},
+        resources::{LookSensitivity, LookSpeed, WalkSpeed},
     },
     standard_box::StandardBoxEvent,
 };
 use bevy::{input::mouse::MouseMotion, prelude::*};
 use bevy_mod_raycast::{RayCastMesh, RayCastSource};
 use heron::prelude::*;
+use std::collections::HashSet;
 
 pub fn walk(
     mut velocity_query: Query<&mut Velocity, (With<Player>, With<Strafes>)>,
     turn_query: Query<&Turn, With<Player>>,
-    input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
     walk_speed: Res<WalkSpeed>,
 ) {
     for mut velocity in velocity_query.iter_mut() {
-        let mut linear = Vec3::ZERO;
-
-        if input.pressed(KeyCode::W) {
-            linear += Vec3::new(0., 0., -1.);
-        }
-        if input.pressed(KeyCode::A) {
-            linear += Vec3::new(-1., 0., 0.);
-        }
-        if input.pressed(KeyCode::S) {
-            linear += Vec3::new(0., 0., 1.);
-        }
-        if input.pressed(KeyCode::D) {
-            linear += Vec3::new(1., 0., 0.);
-        }
+        let mut linear = Vec3::new(input_map.axis(WALK_X), 0., -input_map.axis(WALK_Y));
         if linear.length() > 1.0 {
             linear.normalize();
         }
@@ -50,11 +40,18 @@ pub fn camera_tilt(
     mut tilt_query: Query<(&mut Transform, &mut Tilt), With<Player>>,
     time: Res<Time>,
     look_sensitivity: Res<LookSensitivity>,
+    look_speed: Res<LookSpeed>,
+    input_map: Res<InputMap>,
     mut mouse_listener: EventReader<MouseMotion>,
 ) {
-    for motion_event in mouse_listener.iter() {
+    let mouse_delta: f32 = mouse_listener
+        .iter()
+        .map(|motion_event| motion_event.delta.y * -1. * look_sensitivity.0)
+        .sum();
+    let delta = (mouse_delta + input_map.axis(LOOK_Y) * look_speed.0) * time.delta_seconds();
+    if delta != 0. {
         for (mut transform, mut tilt) in tilt_query.iter_mut() {
-            tilt.update(motion_event.delta.y * -1. * look_sensitivity.0 * time.delta_seconds());
+            tilt.update(delta);
 
             transform.rotation = Quat::from(*tilt);
         }
@@ -65,11 +62,18 @@ pub fn body_turn(
     mut turn_query: Query<(&mut Transform, &mut Turn), With<Player>>,
     time: Res<Time>,
     look_sensitivity: Res<LookSensitivity>,
+    look_speed: Res<LookSpeed>,
+    input_map: Res<InputMap>,
     mut mouse_listener: EventReader<MouseMotion>,
 ) {
-    for motion_event in mouse_listener.iter() {
+    let mouse_delta: f32 = mouse_listener
+        .iter()
+        .map(|motion_event| motion_event.delta.x * -1. * look_sensitivity.0)
+        .sum();
+    let delta = (mouse_delta - input_map.axis(LOOK_X) * look_speed.0) * time.delta_seconds();
+    if delta != 0. {
         for (mut transform, mut turn) in turn_query.iter_mut() {
-            turn.update(motion_event.delta.x * -1. * look_sensitivity.0 * time.delta_seconds());
+            turn.update(delta);
 
             transform.rotation = Quat::from(*turn);
         }
@@ -78,17 +82,130 @@ pub fn body_turn(
 
 pub fn box_interaction(
     picking_query: Query<&RayCastSource<BoxRayCastSet>, With<Player>>,
-    box_query: Query<Entity, (With<BoxState>, With<RayCastMesh<BoxRayCastSet>>)>,
-    input: Res<Input<KeyCode>>,
+    box_query: Query<
+        Option<&Completed>,
+        (
+            With<BoxState>,
+            With<RayCastMesh<BoxRayCastSet>>,
+            Without<Locked>,
+        ),
+    >,
+    input_map: Res<InputMap>,
+    campaign: Res<Campaign>,
     mut writer: EventWriter<StandardBoxEvent>,
 ) {
     for picking_camera in picking_query.iter() {
         if let Some((picked_entity, intersection)) = picking_camera.intersect_top() {
-            if box_query.get(picked_entity).is_ok() && intersection.distance() <= 4.5 {
-                if input.just_pressed(KeyCode::E) {
+            let enterable = match box_query.get(picked_entity) {
+                Ok(Some(Completed)) => campaign.completed_boxes == CompletedPolicy::Replayable,
+                Ok(None) => true,
+                Err(_) => false,
+            };
+            if enterable && intersection.distance() <= 4.5 {
+                if input_map.just_pressed(INTERACT) {
                     writer.send(StandardBoxEvent::Enter(picked_entity))
                 }
             }
         }
     }
 }
+
+/// The ids of every level whose box has been completed.
+fn completed_levels<'a>(completed_query: &'a Query<&LevelId, With<Completed>>) -> HashSet<&'a str> {
+    completed_query
+        .iter()
+        .map(|level_id| level_id.0.as_str())
+        .collect()
+}
+
+/// Keeps boxes Locked for as long as the levels their LevelEntry requires aren't all completed.
+pub fn lock_boxes(
+    mut commands: Commands,
+    box_query: Query<(Entity, &LevelId, Option<&Locked>), With<BoxState>>,
+    completed_query: Query<&LevelId, With<Completed>>,
+    campaign: Res<Campaign>,
+) {
+    let completed = completed_levels(&completed_query);
+    for (box_, level_id, locked) in box_query.iter() {
+        let unlocked = campaign.get(&level_id.0).map_or(true, |level| {
+            level
+                .requires
+                .iter()
+                .all(|required| completed.contains(required.as_str()))
+        });
+        match (unlocked, locked) {
+            (false, None) => {
+                commands.entity(box_).insert(Locked);
+            }
+            (true, Some(Locked)) => {
+                commands.entity(box_).remove::<Locked>();
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Removes or reveals props once the levels they require are completed.
+pub fn unlock_props(
+    mut commands: Commands,
+    prop_query: Query<(Entity, &Prop)>,
+    completed_query: Query<&LevelId, With<Completed>>,
+    mut visible_query: Query<&mut Visible>,
+    campaign: Res<Campaign>,
+) {
+    let completed = completed_levels(&completed_query);
+    for (entity, prop) in prop_query.iter() {
+        let entry = &campaign.props[prop.index];
+        if !entry
+            .requires
+            .iter()
+            .all(|required| completed.contains(required.as_str()))
+        {
+            continue;
+        }
+
+        match entry.effect {
+            UnlockEffect::Remove => commands.entity(entity).despawn_recursive(),
+            UnlockEffect::Spawn => {
+                if let Ok(mut visible) = visible_query.get_mut(entity) {
+                    visible.is_visible = true;
+                }
+                commands
+                    .entity(entity)
+                    .remove::<Prop>()
+                    .insert(BodyType::Static)
+                    .insert(Body::Cuboid {
+                        half_extends: entry.size / 2.,
+                    });
+            }
+        }
+    }
+}
+
+/// Colors the cube each box sits on by whether the box is Locked or Completed.
+pub fn render_box_status(
+    box_query: Query<(&Parent, Option<&Locked>, Option<&Completed>), With<BoxState>>,
+    material_query: Query<&Handle<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (parent, locked, completed) in box_query.iter() {
+        let (base_color, emissive) = match (locked, completed) {
+            (Some(Locked), _) => (Color::rgb(0.3, 0.3, 0.3), Color::BLACK),
+            (None, Some(Completed)) => (Color::rgb(0.6, 0.9, 0.6), Color::rgb(0.1, 0.3, 0.1)),
+            (None, None) => (Color::WHITE, Color::BLACK),
+        };
+
+        if let Ok(handle) = material_query.get(parent.0) {
+            // Only touch the material when it changes, since that re-uploads it
+            let up_to_date = materials.get(handle).map_or(true, |material| {
+                material.base_color == base_color && material.emissive == emissive
+            });
+            if !up_to_date {
+                if let Some(material) = materials.get_mut(handle) {
+                    material.base_color = base_color;
+                    material.emissive = emissive;
+                }
+            }
+        }
+    }
+}
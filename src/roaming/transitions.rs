@@ -1,19 +1,25 @@
 use crate::{
     box_internal::{components::*, BoxData},
+    campaign::{Campaign, LevelEntry, UnlockEffect},
     roaming::components::*,
+    save::SaveFile,
     standard_box::{components::Active, StandardBoxEvent},
-    AppState, LEVEL_ORDER,
+    AppState,
 };
-use bevy::prelude::*;
+use bevy::{asset::LoadState, prelude::*};
 use bevy_mod_raycast::{BoundVol, RayCastMesh, RayCastSource};
 use heron::prelude::*;
-use std::convert::TryFrom;
 
-pub fn camera_setup(mut commands: Commands) {
+pub fn camera_setup(mut commands: Commands, save_file: Res<SaveFile>) {
     commands.spawn_bundle(UiCameraBundle::default());
 
+    let saved = save_file.game.player;
+    let translation = saved.map_or(Vec3::new(0., 1., 2.), |saved| saved.translation);
     commands
-        .spawn_bundle((Transform::from_xyz(0., 1., 2.), GlobalTransform::identity()))
+        .spawn_bundle((
+            Transform::from_translation(translation),
+            GlobalTransform::identity(),
+        ))
         .insert(Body::Capsule {
             radius: 0.5,
             half_segment: 1.,
@@ -23,13 +29,24 @@ pub fn camera_setup(mut commands: Commands) {
         .insert(Player)
         .insert(Strafes)
         .with_children(|parent| {
+            let turn = saved.map_or(Turn::default(), |saved| saved.turn);
             parent
-                .spawn_bundle((Transform::default(), GlobalTransform::identity()))
-                .insert(Turn::default())
+                .spawn_bundle((
+                    Transform::from_rotation(Quat::from(turn)),
+                    GlobalTransform::identity(),
+                ))
+                .insert(turn)
                 .insert(Player)
                 .with_children(|parent| {
-                    let transform = Transform::from_xyz(0., 0.8, 0.)
+                    let mut transform = Transform::from_xyz(0., 0.8, 0.)
                         .looking_at(Vec3::new(0., 0., -1.), Vec3::Y);
+                    let tilt = match saved {
+                        Some(saved) => {
+                            transform.rotation = Quat::from(saved.tilt);
+                            saved.tilt
+                        }
+                        None => Tilt::new(transform.rotation.to_axis_angle().1 * -1.),
+                    };
 
                     parent
                         .spawn_bundle(PerspectiveCameraBundle {
@@ -37,7 +54,7 @@ pub fn camera_setup(mut commands: Commands) {
                             ..Default::default()
                         })
                         .insert(Player)
-                        .insert(Tilt::new(transform.rotation.to_axis_angle().1 * -1.))
+                        .insert(tilt)
                         .insert(RayCastSource::<BoxRayCastSet>::new_transform_empty());
                 });
         });
@@ -73,40 +90,181 @@ pub fn grab_cursor(mut windows: ResMut<Windows>) {
 
 const BOX_DISTANCE: f32 = 2.;
 
-pub fn light_setup(mut commands: Commands) {
-    let num_lights = LEVEL_ORDER.len() / 3;
-    let total_length = (LEVEL_ORDER.len() - 1) as f32 * BOX_DISTANCE;
-    let light_distance = total_length / (num_lights - 1) as f32;
+/// Where the box for the level at the given index in the Campaign should be placed.
+fn level_translation(index: usize, level: &LevelEntry) -> Vec3 {
+    level
+        .position
+        .unwrap_or_else(|| Vec3::new(index as f32 * BOX_DISTANCE, 0.5, 0.))
+}
+
+pub fn light_setup(mut commands: Commands, campaign: Res<Campaign>) {
+    let translations: Vec<Vec3> = campaign
+        .levels
+        .iter()
+        .enumerate()
+        .map(|(i, level)| level_translation(i, level))
+        .collect();
+    let (first, last) = match (translations.first(), translations.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return,
+    };
+
+    let num_lights = (translations.len() / 3).max(1);
     for i in 0..num_lights {
+        let t = if num_lights > 1 {
+            i as f32 / (num_lights - 1) as f32
+        } else {
+            0.5
+        };
+        let translation = first.lerp(last, t);
         commands.spawn_bundle(LightBundle {
-            transform: Transform::from_xyz(i as f32 * light_distance, 10., 0.),
+            transform: Transform::from_xyz(translation.x, 10., translation.z),
+            ..Default::default()
+        });
+    }
+}
+
+/// Starts loading every level in the Campaign, and marks where their boxes belong.
+/// The boxes themselves are spawned by spawn_loaded_boxes once their BoxData is available.
+pub fn black_box_setup(mut commands: Commands, campaign: Res<Campaign>, server: Res<AssetServer>) {
+    for (i, level) in campaign.levels.iter().enumerate() {
+        let handle: Handle<BoxData> = server.load(format!("levels/{}", level.path).as_str());
+        commands
+            .spawn_bundle((
+                Transform::from_translation(level_translation(i, level)),
+                GlobalTransform::identity(),
+            ))
+            .insert(handle)
+            .insert(LevelId(level.id.clone()))
+            .insert(LevelSlot::default());
+    }
+}
+
+/// Spawns every prop in the Campaign, waiting on the levels it requires.
+/// Props that are removed once unlocked start out solid, and props that appear start out hidden
+/// and without a Body.
+pub fn prop_setup(
+    mut commands: Commands,
+    campaign: Res<Campaign>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (i, prop) in campaign.props.iter().enumerate() {
+        let mut entity = commands.spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Box::new(
+                prop.size.x,
+                prop.size.y,
+                prop.size.z,
+            ))),
+            material: materials.add(StandardMaterial {
+                base_color: Color::GRAY,
+                ..Default::default()
+            }),
+            transform: Transform::from_translation(prop.position),
+            visible: Visible {
+                is_visible: prop.effect == UnlockEffect::Remove,
+                ..Default::default()
+            },
             ..Default::default()
         });
+        entity.insert(Prop { index: i });
+        if prop.effect == UnlockEffect::Remove {
+            entity.insert(BodyType::Static).insert(Body::Cuboid {
+                half_extends: prop.size / 2.,
+            });
+        }
     }
 }
 
-pub fn black_box_setup(
+/// Spawns a box for every LevelSlot whose BoxData has just loaded, and respawns them when their
+/// BoxData is modified (for example, when a level file is edited while the game is running).
+///
+/// If the box being respawned is the Active one, the player is popped back out of it.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_loaded_boxes(
     mut commands: Commands,
+    mut slot_query: Query<(&mut LevelSlot, &Handle<BoxData>, &LevelId, &Transform)>,
+    active_query: Query<(Entity, &Parent), With<Active>>,
+    mut asset_events: EventReader<AssetEvent<BoxData>>,
+    mut standard_writer: EventWriter<StandardBoxEvent>,
+    box_datas: Res<Assets<BoxData>>,
     server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut standard_materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    for (i, level) in LEVEL_ORDER.iter().enumerate() {
-        let level_data =
-            BoxData::try_from(*level).unwrap_or_else(|_| panic!("Unable to load level {}", 1));
-        spawn_box(
-            &level_data,
-            Transform::from_xyz(i as f32 * BOX_DISTANCE, 0.5, 0.),
-            &mut commands,
-            &server,
-            &mut meshes,
-            &mut standard_materials,
-        );
+    for event in asset_events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+        let level_data = match box_datas.get(handle) {
+            Some(level_data) => level_data,
+            None => continue,
+        };
+
+        for (mut slot, slot_handle, level_id, transform) in slot_query.iter_mut() {
+            if slot_handle != handle {
+                continue;
+            }
+
+            if let Some(old_box) = slot.box_ {
+                for (active, parent) in active_query.iter() {
+                    if parent.0 == old_box {
+                        standard_writer.send(StandardBoxEvent::Exit(active));
+                    }
+                }
+                commands.entity(old_box).despawn_recursive();
+            }
+
+            slot.box_ = Some(spawn_box(
+                level_data,
+                level_id.clone(),
+                *transform,
+                &mut commands,
+                &server,
+                &mut meshes,
+                &mut standard_materials,
+            ));
+        }
+    }
+}
+
+/// Spawns a placeholder for every LevelSlot whose BoxData failed to load, so one broken level
+/// doesn't take down the rest of the game.
+/// The AssetServer logs the LevelLoadError itself, and the placeholder is replaced by
+/// spawn_loaded_boxes if the level is fixed while the game is running.
+pub fn spawn_load_failure_placeholders(
+    mut commands: Commands,
+    mut slot_query: Query<(&mut LevelSlot, &Handle<BoxData>, &Transform)>,
+    server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (mut slot, handle, transform) in slot_query.iter_mut() {
+        if slot.box_.is_none() && server.get_load_state(handle) == LoadState::Failed {
+            let placeholder = commands
+                .spawn_bundle(PbrBundle {
+                    mesh: meshes.add(Mesh::from(shape::Cube::new(1.))),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::rgb(0.8, 0.2, 0.2),
+                        ..Default::default()
+                    }),
+                    transform: *transform,
+                    ..Default::default()
+                })
+                .insert(BodyType::Static)
+                .insert(Body::Cuboid {
+                    half_extends: Vec3::new(0.5, 0.5, 0.5),
+                })
+                .id();
+            slot.box_ = Some(placeholder);
+        }
     }
 }
 
 pub fn spawn_box(
     level_data: &BoxData,
+    level_id: LevelId,
     base_transform: Transform,
     commands: &mut Commands,
     server: &Res<AssetServer>,
@@ -138,8 +296,11 @@ pub fn spawn_box(
                     transform: Transform::from_xyz(0., 0.625, 0.),
                     ..Default::default()
                 })
-                .insert(BoxState::default())
-                .insert(Progression::new(level_data.prompt.clone()))
+                .insert(BoxState::new(&level_data.memory))
+                .insert(level_data.step_limit)
+                .insert(level_id)
+                .insert(Progression::new(level_data.prompt.clone(), level_data.goal))
+                .insert(BoxScripts::new(level_data))
                 .insert(RayCastMesh::<BoxRayCastSet>::default())
                 .insert(BoundVol::default())
                 .with_children(|parent| {
@@ -0,0 +1,229 @@
+//! Provides the save game, which keeps the player's progress between runs.
+//!
+//! The SaveFile resource keeps a SaveGame up to date with every box and the player, and writes it
+//! to disk as soon as a box is completed, and every AUTOSAVE_SECONDS while boxes are used or the
+//! player moves around.
+//! Boxes and the player are restored from it as they're spawned.
+use crate::{
+    box_internal::components::{BoxState, Completed, LevelId, Progression},
+    roaming::components::{Player, Strafes, Tilt, Turn},
+};
+use bevy::{log::warn, prelude::*};
+use ron::{
+    de::from_reader,
+    ser::{to_string_pretty, PrettyConfig},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+/// How often boxes and the player's position are saved while they change.
+pub const AUTOSAVE_SECONDS: f32 = 5.;
+
+/// Where the game is saved unless another path is given: `black-box/save.ron` in the platform's
+/// data directory, or the working directory if there isn't one.
+pub fn default_save_path() -> PathBuf {
+    match dirs::data_dir() {
+        Some(dir) => dir.join("black-box").join("save.ron"),
+        None => PathBuf::from("save.ron"),
+    }
+}
+
+/// The saved state of a single box.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoxSave {
+    #[serde(default)]
+    pub completed: bool,
+    pub state: BoxState,
+    pub progression: Progression,
+}
+
+impl BoxSave {
+    /// Whether this can be restored onto a box that was spawned with state and progression.
+    /// It can't if the level has changed shape since the game was saved.
+    pub fn fits(&self, state: &BoxState, progression: &Progression) -> bool {
+        self.progression.get_prompt() == progression.get_prompt()
+            && self.progression.goal() == progression.goal()
+            && self.state.same_layout(state)
+    }
+}
+
+/// The saved position and orientation of the player.
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerSave {
+    pub translation: Vec3,
+    pub turn: Turn,
+    pub tilt: Tilt,
+}
+
+/// Everything that's saved, with boxes keyed by their LevelId.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SaveGame {
+    #[serde(default)]
+    pub boxes: BTreeMap<String, BoxSave>,
+    #[serde(default)]
+    pub player: Option<PlayerSave>,
+}
+
+impl SaveGame {
+    pub fn load<P: AsRef<Path>>(path: P) -> ron::error::Result<SaveGame> {
+        let f = File::open(path)?;
+        from_reader(f)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> ron::error::Result<()> {
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let ron = to_string_pretty(self, PrettyConfig::default())?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+}
+
+/// Resource with the SaveGame, and the path it's written to.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SaveFile {
+    path: PathBuf,
+    pub game: SaveGame,
+    /// Whether game has changed in a way that should be written right away.
+    unsaved: bool,
+    /// Whether game has changed in a way that can wait for the next autosave.
+    stale: bool,
+    since_written: f32,
+}
+
+impl SaveFile {
+    /// Load the SaveGame at path, starting a new one if there isn't one there.
+    ///
+    /// If the SaveGame can't be read, a warning is logged and a new one is started, which will
+    /// overwrite it.
+    pub fn load<P: Into<PathBuf>>(path: P) -> SaveFile {
+        let path = path.into();
+        let game = if path.exists() {
+            SaveGame::load(&path).unwrap_or_else(|e| {
+                warn!("Unable to load save from {}: {}", path.display(), e);
+                SaveGame::default()
+            })
+        } else {
+            SaveGame::default()
+        };
+
+        SaveFile {
+            path,
+            game,
+            unsaved: false,
+            stale: false,
+            since_written: 0.,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Serialize, Deserialize)]
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(sync_boxes.system())
+            .add_system(save_completions.system())
+            .add_system(save_player.system())
+            .add_system(write_save.system());
+    }
+}
+
+/// Restores newly spawned boxes from the SaveFile, and updates it whenever they change, to be
+/// written at the next autosave.
+pub fn sync_boxes(
+    mut commands: Commands,
+    mut box_query: Query<
+        (
+            Entity,
+            &LevelId,
+            &mut BoxState,
+            &mut Progression,
+            ChangeTrackers<LevelId>,
+        ),
+        Or<(Changed<BoxState>, Changed<Progression>)>,
+    >,
+    mut save_file: ResMut<SaveFile>,
+) {
+    for (box_, level_id, mut state, mut progression, level_id_tracker) in box_query.iter_mut() {
+        if level_id_tracker.is_added() {
+            if let Some(saved) = save_file.game.boxes.get(&level_id.0) {
+                if saved.completed {
+                    commands.entity(box_).insert(Completed);
+                }
+                if saved.fits(&state, &progression) {
+                    *state = saved.state.clone();
+                    *progression = saved.progression.clone();
+                }
+            }
+        }
+
+        let saved = save_file.game.boxes.entry(level_id.0.clone()).or_default();
+        if saved.state != *state || saved.progression != *progression {
+            saved.state = state.clone();
+            saved.progression = progression.clone();
+            save_file.stale = true;
+        }
+    }
+}
+
+pub fn save_completions(
+    box_query: Query<&LevelId, Added<Completed>>,
+    mut save_file: ResMut<SaveFile>,
+) {
+    for level_id in box_query.iter() {
+        let saved = save_file.game.boxes.entry(level_id.0.clone()).or_default();
+        if !saved.completed {
+            saved.completed = true;
+            save_file.unsaved = true;
+        }
+    }
+}
+
+pub fn save_player(
+    body_query: Query<&Transform, (With<Player>, With<Strafes>)>,
+    turn_query: Query<&Turn, With<Player>>,
+    tilt_query: Query<&Tilt, With<Player>>,
+    mut save_file: ResMut<SaveFile>,
+) {
+    if let (Ok(transform), Ok(turn), Ok(tilt)) = (
+        body_query.single(),
+        turn_query.single(),
+        tilt_query.single(),
+    ) {
+        let player = Some(PlayerSave {
+            translation: transform.translation,
+            turn: *turn,
+            tilt: *tilt,
+        });
+        if save_file.game.player != player {
+            save_file.game.player = player;
+            save_file.stale = true;
+        }
+    }
+}
+
+pub fn write_save(mut save_file: ResMut<SaveFile>, time: Res<Time>) {
+    save_file.since_written += time.delta_seconds();
+    let autosave = save_file.stale && save_file.since_written >= AUTOSAVE_SECONDS;
+    if save_file.unsaved || autosave {
+        save_file.unsaved = false;
+        save_file.stale = false;
+        save_file.since_written = 0.;
+        if let Err(e) = save_file.game.save(&save_file.path) {
+            warn!("Unable to save game to {}: {}", save_file.path.display(), e);
+        }
+    }
+}
@@ -11,14 +11,14 @@ use crate::{box_internal, AppState, SystemLabels};
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-pub const BUTTON_NUMS: [KeyCode; 6] = [
-    KeyCode::Key1,
-    KeyCode::Key2,
-    KeyCode::Key3,
-    KeyCode::Key4,
-    KeyCode::Key5,
-    KeyCode::Key6,
-];
+/// Font size of the output display, used as long as the output fits across it.
+pub const DISPLAY_FONT_SIZE: f32 = 200.;
+/// How many characters fit across the output display at DISPLAY_FONT_SIZE.
+pub const DISPLAY_FIT_CHARS: usize = 6;
+/// Font size of the prompt pieces, used as long as the whole prompt fits across the screen.
+pub const PROMPT_FONT_SIZE: f32 = 50.;
+/// How many characters of the prompt fit across the screen at PROMPT_FONT_SIZE.
+pub const PROMPT_FIT_CHARS: usize = 24;
 
 pub enum StandardBoxEvent {
     Enter(Entity),
@@ -32,12 +32,14 @@ impl Plugin for StandardBoxPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_system_set(
             SystemSet::on_enter(AppState::StandardBox)
-                .with_system(transitions::spawn_box_ui.system()),
+                .with_system(transitions::spawn_box_ui.system())
+                .with_system(transitions::trigger_enter.system()),
         )
         .add_system_set(
             SystemSet::on_update(AppState::StandardBox)
                 .label(SystemLabels::InputLabel)
-                .with_system(systems::button_input.system()),
+                .with_system(systems::button_input.system())
+                .with_system(systems::tick_active_box.system()),
         )
         .add_system_set(
             SystemSet::on_update(AppState::StandardBox)
@@ -56,6 +58,7 @@ impl Plugin for StandardBoxPlugin {
         .add_system_set(
             SystemSet::on_exit(AppState::StandardBox)
                 .with_system(transitions::despawn_box_ui.system())
+                .with_system(transitions::trigger_exit.system())
                 .with_system(transitions::deactivate_box.system()),
         );
     }
@@ -1,12 +1,13 @@
 use crate::{
     box_internal::{
-        components::{ActionScript, Itemized, Pressable, Progression},
-        OutputEvent,
+        components::{ActionScript, BoxScripts, Itemized, Pressable, Progression},
+        OutputEvent, TriggerEvent,
     },
+    input_map::{box_button, InputMap},
     resources::ColorHandles,
     standard_box::{
         components::{Active, BoxOutDisplay, BoxReference, ProgressionPiece},
-        BUTTON_NUMS,
+        DISPLAY_FIT_CHARS, DISPLAY_FONT_SIZE,
     },
 };
 use bevy::prelude::*;
@@ -14,16 +15,25 @@ use bevy::prelude::*;
 pub fn button_input(
     mut button_query: Query<(&mut Pressable, &Itemized), With<ActionScript>>,
     active_entities: Query<Entity, With<Active>>,
-    input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
 ) {
-    if input.is_changed() {
-        for (mut pressable, itemized) in button_query.iter_mut() {
-            let pressed = input.pressed(BUTTON_NUMS[itemized.index]);
-            if pressable.update_necessary(pressed)
-                && active_entities.get(itemized.collector).is_ok()
-            {
-                pressable.update(input.pressed(BUTTON_NUMS[itemized.index]));
-            }
+    for (mut pressable, itemized) in button_query.iter_mut() {
+        let pressed = input_map.pressed(&box_button(itemized.index));
+        if pressable.update_necessary(pressed) && active_entities.get(itemized.collector).is_ok() {
+            pressable.update(pressed);
+        }
+    }
+}
+
+/// Asks for the tick scripts of the Active box to be run as they come due.
+pub fn tick_active_box(
+    mut box_query: Query<(Entity, &mut BoxScripts), With<Active>>,
+    mut trigger_writer: EventWriter<TriggerEvent>,
+    time: Res<Time>,
+) {
+    for (box_, mut scripts) in box_query.iter_mut() {
+        for trigger in scripts.advance(time.delta_seconds()) {
+            trigger_writer.send(TriggerEvent { box_, trigger });
         }
     }
 }
@@ -40,6 +50,11 @@ pub fn render_button(
     }
 }
 
+/// Scale size down so that chars characters take up no more room than fit_chars would at size.
+pub fn fit_font_size(size: f32, fit_chars: usize, chars: usize) -> f32 {
+    size * fit_chars as f32 / chars.max(fit_chars) as f32
+}
+
 pub fn render_display(
     mut display_query: Query<(&BoxReference, &mut Text), With<BoxOutDisplay>>,
     mut event_reader: EventReader<OutputEvent>,
@@ -48,7 +63,10 @@ pub fn render_display(
     for (box_ref, mut text) in display_query.iter_mut() {
         for output_event in event_reader.iter() {
             if output_event.box_ == box_ref.box_ {
-                text.sections[0].value = output_event.output.to_string();
+                let value = output_event.output.to_string();
+                text.sections[0].style.font_size =
+                    fit_font_size(DISPLAY_FONT_SIZE, DISPLAY_FIT_CHARS, value.chars().count());
+                text.sections[0].value = value;
                 text.sections[0].style.color.set_a(1.);
             }
         }
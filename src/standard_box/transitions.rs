@@ -1,11 +1,13 @@
 use crate::{
     box_internal::{
-        components::{BoxState, Itemized, Progression},
-        BoxCompletedEvent,
+        components::{BoxState, Itemized, Progression, Trigger},
+        BoxCompletedEvent, TriggerEvent,
     },
+    input_map::{InputMap, LEAVE_BOX},
     standard_box::{
         components::{Active, BoxOutDisplay, BoxReference, BoxUiRoot, ProgressionPiece},
-        StandardBoxEvent,
+        systems::fit_font_size,
+        StandardBoxEvent, DISPLAY_FONT_SIZE, PROMPT_FIT_CHARS, PROMPT_FONT_SIZE,
     },
     AppState,
 };
@@ -17,6 +19,32 @@ pub fn despawn_box_ui(mut commands: Commands, ui_query: Query<Entity, With<BoxUi
     }
 }
 
+/// Asks for the on_enter script of the newly Active box to be run.
+pub fn trigger_enter(
+    active_box_query: Query<Entity, (With<Active>, With<BoxState>)>,
+    mut trigger_writer: EventWriter<TriggerEvent>,
+) {
+    for box_ in active_box_query.iter() {
+        trigger_writer.send(TriggerEvent {
+            box_,
+            trigger: Trigger::Enter,
+        });
+    }
+}
+
+/// Asks for the on_exit script of the box that's about to stop being Active to be run.
+pub fn trigger_exit(
+    active_box_query: Query<Entity, (With<Active>, With<BoxState>)>,
+    mut trigger_writer: EventWriter<TriggerEvent>,
+) {
+    for box_ in active_box_query.iter() {
+        trigger_writer.send(TriggerEvent {
+            box_,
+            trigger: Trigger::Exit,
+        });
+    }
+}
+
 pub fn deactivate_box(
     mut commands: Commands,
     active_box_query: Query<Entity, (With<Active>, Or<(With<BoxState>, With<Progression>)>)>,
@@ -34,6 +62,9 @@ pub fn spawn_box_ui(
 ) {
     let transparent = materials.add(ColorMaterial::color(Color::NONE));
     for (box_entity, progression) in active_prog_query.iter() {
+        let prompt = progression.get_prompt();
+        let prompt_chars = prompt.iter().map(|o| o.to_string().chars().count()).sum();
+        let prompt_font_size = fit_font_size(PROMPT_FONT_SIZE, PROMPT_FIT_CHARS, prompt_chars);
         commands
             .spawn_bundle(NodeBundle {
                 style: Style {
@@ -65,7 +96,8 @@ pub fn spawn_box_ui(
                         ..Default::default()
                     })
                     .with_children(|parent| {
-                        for (i, piece) in progression.get_prompt().iter().enumerate() {
+                        for (i, piece) in prompt.iter().enumerate() {
+                            // Pieces are square, unless they need to be wider to fit their output.
                             parent
                                 .spawn_bundle(NodeBundle {
                                     style: Style {
@@ -73,7 +105,16 @@ pub fn spawn_box_ui(
                                         justify_content: JustifyContent::Center,
                                         size: Size {
                                             height: Val::Percent(100.),
+                                            width: Val::Auto,
+                                        },
+                                        min_size: Size {
                                             width: Val::Percent(10. / 16. * 9.),
+                                            ..Default::default()
+                                        },
+                                        padding: Rect {
+                                            left: Val::Px(prompt_font_size / 5.),
+                                            right: Val::Px(prompt_font_size / 5.),
+                                            ..Default::default()
                                         },
                                         ..Default::default()
                                     },
@@ -92,7 +133,7 @@ pub fn spawn_box_ui(
                                                 piece.to_string(),
                                                 TextStyle {
                                                     font: font.clone(),
-                                                    font_size: 50.,
+                                                    font_size: prompt_font_size,
                                                     color: Color::rgb(0.1, 0.1, 0.1),
                                                 },
                                                 TextAlignment::default(),
@@ -125,7 +166,7 @@ pub fn spawn_box_ui(
                                     "".to_string(),
                                     TextStyle {
                                         font: font.clone(),
-                                        font_size: 200.,
+                                        font_size: DISPLAY_FONT_SIZE,
                                         color: Color::rgb(0.36, 0.63, 0.36),
                                     },
                                     TextAlignment::default(),
@@ -151,14 +192,10 @@ pub fn exit_on_level_completion(
 
 pub fn exit_on_walk_away(
     active_box_query: Query<Entity, (With<BoxState>, With<Active>)>,
-    input: Res<Input<KeyCode>>,
+    input_map: Res<InputMap>,
     mut standard_writer: EventWriter<StandardBoxEvent>,
 ) {
-    if input.just_pressed(KeyCode::W)
-        || input.just_pressed(KeyCode::A)
-        || input.just_pressed(KeyCode::S)
-        || input.just_pressed(KeyCode::D)
-    {
+    if input_map.just_pressed(LEAVE_BOX) {
         for active_box in active_box_query.iter() {
             standard_writer.send(StandardBoxEvent::Exit(active_box));
         }
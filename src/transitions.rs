@@ -7,3 +7,10 @@ pub fn add_colors(mut materials: ResMut<Assets<ColorMaterial>>, mut commands: Co
         green: materials.add(ColorMaterial::color(Color::rgb(0.36, 0.63, 0.36))),
     });
 }
+
+/// Allows levels to be hot-reloaded while the game is running.
+pub fn watch_for_changes(server: Res<AssetServer>) {
+    server
+        .watch_for_changes()
+        .expect("Unable to watch assets for changes");
+}